@@ -0,0 +1,121 @@
+use crate::loaders::euroscope::position::{Position, Valid};
+
+use super::RunwayIdentifier;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoordinationPointType {
+    Copx,
+    FirCopx,
+}
+
+/// A coordination point (`COPX` / `FIR_COPX`) from the [AIRSPACE] section.
+///
+/// Fields given as `*` in the ESE are stored as `None`, meaning the agreement applies to any value.
+#[derive(Debug, Clone)]
+pub struct CoordinationPoint {
+    pub point_type: CoordinationPointType,
+    pub departure_airport: Option<String>,
+    pub departure_runway: Option<RunwayIdentifier>,
+    pub previous_fix: Option<String>,
+    pub fix: String,
+    pub next_fix: Option<String>,
+    pub arrival_airport: Option<String>,
+    pub arrival_runway: Option<RunwayIdentifier>,
+    pub from_sector: String,
+    pub to_sector: String,
+    pub climb_level: Option<u32>,
+    pub descent_level: Option<u32>,
+    pub name: String,
+}
+impl CoordinationPoint {
+    /// Whether this point covers traffic handed over from `from_sector` to `to_sector`.
+    pub fn is_between(&self, from_sector: &str, to_sector: &str) -> bool {
+        self.from_sector.eq_ignore_ascii_case(from_sector) && self.to_sector.eq_ignore_ascii_case(to_sector)
+    }
+
+    /// Whether the departure and arrival airport / runway filters of this point accept the given flight.
+    pub fn applies_to(
+        &self,
+        departure_airport: &str,
+        departure_runway: Option<&RunwayIdentifier>,
+        arrival_airport: &str,
+        arrival_runway: Option<&RunwayIdentifier>,
+    ) -> bool {
+        Self::filters_match(&self.departure_airport, &self.departure_runway, departure_airport, departure_runway)
+            && Self::filters_match(&self.arrival_airport, &self.arrival_runway, arrival_airport, arrival_runway)
+    }
+
+    fn filters_match(
+        airport_filter: &Option<String>,
+        runway_filter: &Option<RunwayIdentifier>,
+        airport: &str,
+        runway: Option<&RunwayIdentifier>,
+    ) -> bool {
+        let airport_matches = airport_filter.as_ref().is_none_or(|filter| filter.eq_ignore_ascii_case(airport));
+        let runway_matches = match (runway_filter, runway) {
+            (Some(filter), Some(runway)) => filter == runway,
+            (Some(_), None) => false,
+            (None, _) => true,
+        };
+        airport_matches && runway_matches
+    }
+}
+
+/// A minimum safe altitude warning area (`MSAW`) from the [AIRSPACE] section.
+///
+/// The `MSAW` line gives the name and minimum altitude in feet; the `COORD` lines that follow give the vertices.
+#[derive(Debug, Clone)]
+pub struct MsawArea {
+    pub name: String,
+    pub altitude: u32,
+    pub vertices: Vec<Position<Valid>>,
+}
+impl MsawArea {
+    /// Whether `position` lies inside the area polygon.
+    pub fn contains(&self, position: Position<Valid>) -> bool {
+        polygon_contains(&self.vertices, position)
+    }
+}
+
+/// A named boundary line (`SECTORLINE`) from the [AIRSPACE] section, made of the `COORD` lines that follow it.
+///
+/// `CIRCLE_SECTORLINE` lines with a centre coordinate are stored as a 36 point polygon.
+#[derive(Debug, Clone)]
+pub struct SectorLine {
+    pub name: String,
+    pub points: Vec<Position<Valid>>,
+}
+
+/// A sector (`SECTOR:name:bottom:top`) from the [AIRSPACE] section, with levels in feet.
+///
+/// `OWNER` lists the positions that own the sector in order of priority, and `BORDER` the sector lines
+/// that form its boundary.
+#[derive(Debug, Clone)]
+pub struct AirspaceSector {
+    pub name: String,
+    pub bottom: u32,
+    pub top: u32,
+    pub owners: Vec<String>,
+    pub border: Vec<String>,
+}
+impl AirspaceSector {
+    pub fn contains_level(&self, altitude: u32) -> bool {
+        self.bottom <= altitude && altitude <= self.top
+    }
+}
+
+/// Ray casting point in polygon test, treating latitude and longitude as planar coordinates.
+pub(crate) fn polygon_contains(vertices: &[Position<Valid>], position: Position<Valid>) -> bool {
+    let mut inside = false;
+    let mut j = vertices.len().wrapping_sub(1);
+    for (i, vertex) in vertices.iter().enumerate() {
+        let other = vertices[j];
+        if (vertex.lat > position.lat) != (other.lat > position.lat)
+            && position.lon < (other.lon - vertex.lon) * (position.lat - vertex.lat) / (other.lat - vertex.lat) + vertex.lon
+        {
+            inside = !inside;
+        }
+        j = i;
+    }
+    inside
+}
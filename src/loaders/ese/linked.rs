@@ -0,0 +1,96 @@
+use crate::loaders::euroscope::{
+    sector::Sector,
+    waypoint::{self as sct, RunwayEnd},
+};
+
+use super::{Ese, Procedure, RunwayIdentifier};
+
+/// An SCT airport together with the ESE procedures for each of its runway ends.
+#[derive(Debug)]
+pub struct LinkedAirport<'a> {
+    pub airport: &'a sct::Airport,
+    pub runway_ends: Vec<LinkedRunwayEnd<'a>>,
+}
+impl LinkedAirport<'_> {
+    pub fn identifier(&self) -> &str {
+        &self.airport.identifier
+    }
+
+    pub fn runway(&self, runway: &RunwayIdentifier) -> Option<&LinkedRunwayEnd<'_>> {
+        self.runway_ends.iter().find(|end| runway.matches(end.runway_end))
+    }
+}
+
+#[derive(Debug)]
+pub struct LinkedRunwayEnd<'a> {
+    pub runway_end: &'a RunwayEnd,
+    pub procedures: Vec<&'a Procedure>,
+}
+
+/// An ESE runway with procedures that has no matching runway (or airport) in the SCT.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingRunway {
+    pub airport: String,
+    pub runway: RunwayIdentifier,
+    pub procedures: Vec<String>,
+}
+
+/// The airports of a [`Sector`] joined with the procedures of its [`Ese`].
+///
+/// Airport identifiers are matched case-insensitively.
+#[derive(Debug, Default)]
+pub struct LinkedAirports<'a> {
+    pub airports: Vec<LinkedAirport<'a>>,
+    pub missing_runways: Vec<MissingRunway>,
+}
+
+impl<'a> LinkedAirports<'a> {
+    pub fn new(sector: &'a Sector, ese: &'a Ese) -> Self {
+        let mut linked = LinkedAirports::default();
+
+        for airport in &sector.airports {
+            let ese_airport = ese.airport(&airport.identifier);
+            let runway_ends = airport
+                .runways
+                .iter()
+                .flat_map(|strip| [&strip.end_a, &strip.end_b])
+                .map(|runway_end| LinkedRunwayEnd {
+                    runway_end,
+                    procedures: ese_airport
+                        .and_then(|ese_airport| ese_airport.runways.get(&RunwayIdentifier::from(runway_end)))
+                        .map(|procedures| procedures.iter().collect())
+                        .unwrap_or_default(),
+                })
+                .collect();
+            linked.airports.push(LinkedAirport { airport, runway_ends });
+        }
+
+        for ese_airport in &ese.sids_stars {
+            let sct_airport = linked
+                .airports
+                .iter()
+                .find(|linked_airport| linked_airport.identifier().eq_ignore_ascii_case(&ese_airport.identifier));
+            let mut missing = ese_airport
+                .runways
+                .iter()
+                .filter(|(runway, _)| sct_airport.is_none_or(|sct_airport| sct_airport.runway(runway).is_none()))
+                .map(|(runway, procedures)| MissingRunway {
+                    airport: ese_airport.identifier.to_string(),
+                    runway: runway.clone(),
+                    procedures: procedures.iter().map(|procedure| procedure.identifier.to_string()).collect(),
+                })
+                .collect::<Vec<_>>();
+            missing.sort_by(|a, b| a.runway.cmp(&b.runway));
+            linked.missing_runways.append(&mut missing);
+        }
+
+        linked
+    }
+
+    /// The linked airport with the given ICAO code (case-insensitive).
+    pub fn airport(&self, identifier: &str) -> Option<&LinkedAirport<'a>> {
+        self.airports
+            .iter()
+            .find(|airport| airport.identifier().eq_ignore_ascii_case(identifier))
+    }
+}
@@ -1,11 +1,18 @@
 use std::{collections::HashMap, fmt::Display, fs::File, io::{BufRead, BufReader}, str::FromStr};
 
+use airspace::{polygon_contains, AirspaceSector, CoordinationPoint, MsawArea, SectorLine};
 use partial::PartialEse;
+use radar::RadarSite;
 
-use super::euroscope::{self, colour::Colour, error::Error, position::{Position, Valid}, waypoint::RunwayModifier};
+use super::euroscope::{self, colour::Colour, error::Error, position::{Position, Valid}, waypoint::{self as sct, RunwayEnd, RunwayModifier}};
 
 
+pub mod airspace;
+pub mod linked;
+pub mod procedure;
+pub mod radar;
 pub mod reader;
+pub mod route;
 pub(crate) mod partial;
 
 
@@ -21,6 +28,11 @@ pub struct Ese {
     pub sids_stars: Vec<Airport>,
     pub non_critical_errors: Vec<(usize, String, Error)>,
     pub atc_positions: Vec<AtcPosition>,
+    pub coordination_points: Vec<CoordinationPoint>,
+    pub msaw_areas: Vec<MsawArea>,
+    pub radar_sites: Vec<RadarSite>,
+    pub sector_lines: Vec<SectorLine>,
+    pub sectors: Vec<AirspaceSector>,
 }
 impl TryFrom<PartialEse> for Ese {
     type Error = Error;
@@ -30,16 +42,140 @@ impl TryFrom<PartialEse> for Ese {
             free_text: value.free_text,
             sids_stars: value.sids_stars,
             atc_positions: value.atc_positions,
+            coordination_points: value.coordination_points,
+            msaw_areas: value.msaw_areas,
+            radar_sites: value.radar_sites,
+            sector_lines: value.sector_lines,
+            sectors: value.sectors,
             non_critical_errors: vec![],
         };
         Ok(ese)
     }
 }
+impl Ese {
+    /// The SIDs and STARs of the airport with the given ICAO code (case-insensitive).
+    pub fn airport(&self, identifier: &str) -> Option<&Airport> {
+        self.sids_stars
+            .iter()
+            .find(|airport| airport.identifier.eq_ignore_ascii_case(identifier))
+    }
+
+    /// All coordination points agreed for a handover from `from_sector` to `to_sector`.
+    pub fn coordination_points_between(&self, from_sector: &str, to_sector: &str) -> Vec<&CoordinationPoint> {
+        self.coordination_points
+            .iter()
+            .filter(|point| point.is_between(from_sector, to_sector))
+            .collect()
+    }
+
+    /// Coordination points that apply along a route through the given sectors, in the order they are crossed.
+    ///
+    /// Each consecutive pair of sectors is looked up with [`Ese::coordination_points_between`].
+    pub fn coordination_points_for_route<S: AsRef<str>>(&self, sectors: &[S]) -> Vec<&CoordinationPoint> {
+        sectors
+            .windows(2)
+            .flat_map(|pair| self.coordination_points_between(pair[0].as_ref(), pair[1].as_ref()))
+            .collect()
+    }
+
+    /// The MSAW area covering `position`. Where areas overlap, the one with the highest minimum altitude is returned.
+    pub fn msaw_at(&self, position: Position<Valid>) -> Option<&MsawArea> {
+        self.msaw_areas
+            .iter()
+            .filter(|area| area.contains(position))
+            .max_by_key(|area| area.altitude)
+    }
+
+    /// Whether an aircraft at `position` and `altitude` (feet) is below the minimum safe altitude for that area.
+    pub fn is_below_msaw(&self, position: Position<Valid>, altitude: u32) -> bool {
+        self.msaw_at(position).is_some_and(|area| altitude < area.altitude)
+    }
+
+    pub fn sector_line(&self, name: &str) -> Option<&SectorLine> {
+        self.sector_lines.iter().find(|line| line.name == name)
+    }
+
+    /// The boundary of `sector`, made by joining its border lines end to end.
+    ///
+    /// Lines are reversed where needed so that each starts where the previous one ended. Unknown lines are skipped.
+    pub fn sector_polygon(&self, sector: &AirspaceSector) -> Vec<Position<Valid>> {
+        let same = |a: Option<&Position<Valid>>, b: Option<&Position<Valid>>| match (a, b) {
+            (Some(a), Some(b)) => (a.lat - b.lat).abs() < 1e-6 && (a.lon - b.lon).abs() < 1e-6,
+            _ => false,
+        };
+        let mut polygon: Vec<Position<Valid>> = Vec::new();
+        for (lines_added, line) in sector.border.iter().filter_map(|name| self.sector_line(name)).enumerate() {
+            let mut points = line.points.clone();
+            let (start, end) = (points.first(), points.last());
+            if !same(polygon.last(), start) {
+                if same(polygon.last(), end) {
+                    points.reverse();
+                } else if lines_added == 1 && same(polygon.first(), start) {
+                    // The first line runs the other way round the sector
+                    polygon.reverse();
+                } else if lines_added == 1 && same(polygon.first(), end) {
+                    polygon.reverse();
+                    points.reverse();
+                }
+            }
+            let skip = usize::from(same(polygon.last(), points.first()));
+            polygon.extend(points.into_iter().skip(skip));
+        }
+        polygon
+    }
+
+    /// The sectors whose boundary contains `position` and, if given, whose levels contain `altitude` (feet).
+    pub fn sectors_at(&self, position: Position<Valid>, altitude: Option<u32>) -> Vec<&AirspaceSector> {
+        self.sectors
+            .iter()
+            .filter(|sector| altitude.is_none_or(|altitude| sector.contains_level(altitude)))
+            .filter(|sector| polygon_contains(&self.sector_polygon(sector), position))
+            .collect()
+    }
+}
 #[derive(Debug)]
 pub struct FreeTextGroup {
     pub name: String,
     pub entries: Vec<FreeText>,
 }
+impl FreeTextGroup {
+    /// Separator between the levels of a hierarchical group name, e.g. `EGLL\Stands`.
+    pub const SEPARATOR: char = '\\';
+
+    /// The levels of the group name, from the top-level group down.
+    pub fn path(&self) -> Vec<&str> {
+        self.name.split(Self::SEPARATOR).collect()
+    }
+
+    /// The full name of the parent group, if this is a sub-group.
+    pub fn parent(&self) -> Option<&str> {
+        self.name.rsplit_once(Self::SEPARATOR).map(|(parent, _)| parent)
+    }
+
+    /// The group named by an ASR free text item, which has the form `<group>\<text>`.
+    pub fn group_of_asr_item(item_name: &str) -> &str {
+        match item_name.rsplit_once(Self::SEPARATOR) {
+            Some(("", _)) => "Default",
+            Some((group, _)) => group,
+            None => item_name,
+        }
+    }
+
+    /// Normalises a group name from the ESE, dropping empty levels and surrounding whitespace.
+    pub(crate) fn normalise_name(name: &str) -> String {
+        let name = name
+            .split(Self::SEPARATOR)
+            .map(str::trim)
+            .filter(|level| !level.is_empty())
+            .collect::<Vec<_>>()
+            .join(&Self::SEPARATOR.to_string());
+        if name.is_empty() {
+            "Default".to_string()
+        } else {
+            name
+        }
+    }
+}
 
 #[derive(Debug)]
 pub struct FreeText {
@@ -54,7 +190,105 @@ pub struct Airport {
     pub runways: HashMap<RunwayIdentifier, Vec<Procedure>>
 }
 
-#[derive(Debug)]
+impl Airport {
+    /// All procedures of `proc_type` for `runway`, sorted by identifier.
+    pub fn procedures(&self, runway: &RunwayIdentifier, proc_type: ProcedureType) -> Vec<&Procedure> {
+        let mut procedures = self
+            .runways
+            .get(runway)
+            .map(|procedures| procedures.iter().filter(|procedure| procedure.proc_type == proc_type).collect::<Vec<_>>())
+            .unwrap_or_default();
+        procedures.sort_by(|a, b| a.identifier.cmp(&b.identifier));
+        procedures
+    }
+
+    pub fn sids(&self, runway: &RunwayIdentifier) -> Vec<&Procedure> {
+        self.procedures(runway, ProcedureType::SID)
+    }
+
+    pub fn stars(&self, runway: &RunwayIdentifier) -> Vec<&Procedure> {
+        self.procedures(runway, ProcedureType::STAR)
+    }
+
+    /// The runways served by the procedure named `identifier` (case-insensitive), sorted.
+    pub fn runways_for_procedure(&self, identifier: &str, proc_type: ProcedureType) -> Vec<&RunwayIdentifier> {
+        let mut runways = self
+            .runways
+            .iter()
+            .filter(|(_, procedures)| {
+                procedures
+                    .iter()
+                    .any(|procedure| procedure.proc_type == proc_type && procedure.identifier.eq_ignore_ascii_case(identifier))
+            })
+            .map(|(runway, _)| runway)
+            .collect::<Vec<_>>();
+        runways.sort();
+        runways
+    }
+
+    /// The SID for `runway` that fits the start of a filed route.
+    ///
+    /// A SID named as the first route element is preferred, otherwise the SID must end at the first waypoint.
+    pub fn sid_for_route(&self, runway: &RunwayIdentifier, route: &str) -> Option<&Procedure> {
+        let first = route_elements(route).into_iter().next()?;
+        let sids = self.sids(runway);
+        sids.iter()
+            .find(|sid| sid.identifier.eq_ignore_ascii_case(first))
+            .or_else(|| sids.iter().find(|sid| sid.last_waypoint().is_some_and(|wp| wp.eq_ignore_ascii_case(first))))
+            .copied()
+    }
+
+    /// The STAR for `runway` that fits the end of a filed route.
+    ///
+    /// A STAR named as the last route element is preferred, otherwise the STAR must start at the last waypoint.
+    pub fn star_for_route(&self, runway: &RunwayIdentifier, route: &str) -> Option<&Procedure> {
+        let last = route_elements(route).into_iter().last()?;
+        let stars = self.stars(runway);
+        stars
+            .iter()
+            .find(|star| star.identifier.eq_ignore_ascii_case(last))
+            .or_else(|| stars.iter().find(|star| star.first_waypoint().is_some_and(|wp| wp.eq_ignore_ascii_case(last))))
+            .copied()
+    }
+
+    /// The SCT runway end for `runway`, looked up in the SCT airport with the same identifier.
+    pub fn runway_end<'a>(&self, runway: &RunwayIdentifier, sct_airport: &'a sct::Airport) -> Option<&'a RunwayEnd> {
+        if !sct_airport.identifier.eq_ignore_ascii_case(&self.identifier) {
+            return None;
+        }
+        sct_airport.runway_end(runway.number, &runway.modifier)
+    }
+}
+
+/// Waypoint, airway and procedure names of a filed route, without `DCT` and speed / level groups.
+pub(crate) fn route_elements(route: &str) -> Vec<&str> {
+    route
+        .split_whitespace()
+        .filter_map(|element| element.split('/').next())
+        .filter(|element| !element.is_empty() && *element != "DCT" && !is_speed_level_group(element))
+        .collect()
+}
+
+/// Whether `value` is a speed / level group such as `N0450F350` or `M082F370`.
+pub(crate) fn is_speed_level_group(value: &str) -> bool {
+    let speed_len = match value.chars().next() {
+        Some('N') | Some('K') => 5,
+        Some('M') => 4,
+        _ => return false,
+    };
+    let (speed, level) = match (value.get(..speed_len), value.get(speed_len..)) {
+        (Some(speed), Some(level)) => (speed, level),
+        _ => return false,
+    };
+    let level_digits = match level.chars().next() {
+        Some('F') | Some('A') => 3,
+        Some('S') | Some('M') => 4,
+        _ => return false,
+    };
+    speed[1..].chars().all(|c| c.is_ascii_digit()) && level.len() == level_digits + 1 && level[1..].chars().all(|c| c.is_ascii_digit())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ProcedureType {
     SID,
     STAR,
@@ -66,6 +300,18 @@ pub struct Procedure {
     pub identifier: String,
     pub route: Vec<String>,
 }
+impl Procedure {
+    /// The waypoints of the route, which the ESE gives as a single space-separated field.
+    pub fn waypoints(&self) -> impl DoubleEndedIterator<Item = &str> {
+        self.route.iter().flat_map(|element| element.split_whitespace())
+    }
+    pub fn first_waypoint(&self) -> Option<&str> {
+        self.waypoints().next()
+    }
+    pub fn last_waypoint(&self) -> Option<&str> {
+        self.waypoints().next_back()
+    }
+}
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
 pub struct RunwayIdentifier {
@@ -92,6 +338,17 @@ impl RunwayIdentifier {
     pub fn number_and_modifier(&self) -> (u8, RunwayModifier) {
         (self.number, self.modifier.clone())
     }
+    pub fn matches(&self, runway_end: &RunwayEnd) -> bool {
+        self.number == runway_end.number && self.modifier == runway_end.modifier
+    }
+}
+impl From<&RunwayEnd> for RunwayIdentifier {
+    fn from(value: &RunwayEnd) -> Self {
+        RunwayIdentifier {
+            number: value.number,
+            modifier: value.modifier.clone(),
+        }
+    }
 }
 impl Display for RunwayIdentifier {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
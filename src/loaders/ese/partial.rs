@@ -1,8 +1,10 @@
 use std::{collections::HashMap, str::FromStr};
 
-use crate::loaders::euroscope::{colour::Colour, error::Error, partial::PositionCreator, SectorResult};
+use aviation_calc_util::{geo::{Bearing, GeoPoint}, units::Length};
 
-use super::{Airport, AtcPosition, FreeText, FreeTextGroup, Procedure, ProcedureType, RunwayIdentifier};
+use crate::loaders::euroscope::{colour::Colour, error::Error, partial::PositionCreator, position::Position, SectorResult};
+
+use super::{airspace::{AirspaceSector, CoordinationPoint, CoordinationPointType, MsawArea, SectorLine}, radar::{RadarCoverage, RadarSite}, Airport, AtcPosition, FreeText, FreeTextGroup, Procedure, ProcedureType, RunwayIdentifier};
 
 #[derive(Default)]
 pub struct PartialEse {
@@ -11,6 +13,22 @@ pub struct PartialEse {
     pub free_text: Vec<FreeTextGroup>,
     pub sids_stars: Vec<Airport>,
     pub atc_positions: Vec<AtcPosition>,
+    pub coordination_points: Vec<CoordinationPoint>,
+    pub msaw_areas: Vec<MsawArea>,
+    pub radar_sites: Vec<RadarSite>,
+    pub sector_lines: Vec<SectorLine>,
+    pub sectors: Vec<AirspaceSector>,
+    current_airspace_block: AirspaceBlock,
+}
+
+/// The multi-line [AIRSPACE] definition that subsequent `COORD` lines belong to.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum AirspaceBlock {
+    #[default]
+    None,
+    Msaw,
+    SectorLine,
+    Sector,
 }
 impl PartialEse {
 
@@ -51,16 +69,13 @@ impl PartialEse {
         let lat = sections.next().ok_or(Error::InvalidFreetext)?;
         let lon = sections.next().ok_or(Error::InvalidFreetext)?;
         let pos = self.position_creator.try_new_from_es(lat, lon)?.validate()?;
-        let mut group_name = sections.next().ok_or(Error::InvalidFreetext)?;
-        if group_name.is_empty() {
-            group_name = "Default";
-        }
+        let group_name = FreeTextGroup::normalise_name(sections.next().ok_or(Error::InvalidFreetext)?);
         let text = sections.next().ok_or(Error::InvalidFreetext)?;
 
         let group = match self.free_text.iter_mut().find(|group| group.name == group_name) {
             Some(group) => group,
             None => {
-                self.free_text.push(FreeTextGroup { name: group_name.to_owned(), entries: Vec::new() });
+                self.free_text.push(FreeTextGroup { name: group_name, entries: Vec::new() });
                 self.free_text.last_mut().unwrap()
             },
         };
@@ -90,7 +105,7 @@ impl PartialEse {
         }
 
         // Find airport or create if it doesn't exist
-        let airport = match self.sids_stars.iter_mut().find(|airport| airport.identifier == icao_identifier) {
+        let airport = match self.sids_stars.iter_mut().find(|airport| airport.identifier.eq_ignore_ascii_case(icao_identifier)) {
             Some(airport) => airport,
             None => {
                 self.sids_stars.push(Airport { identifier: icao_identifier.to_owned(), runways: HashMap::new() });
@@ -173,4 +188,208 @@ impl PartialEse {
 
         Ok(())
     }
+
+    pub fn parse_airspace_line(&mut self, value: &str) -> SectorResult<()> {
+        let keyword = value.split(':').next().unwrap_or_default().to_uppercase();
+        match keyword.as_str() {
+            "COORD" => return self.parse_airspace_coord_line(value),
+            "OWNER" => return self.parse_sector_owner_line(value),
+            "BORDER" => return self.parse_sector_border_line(value),
+            // Further details of the current sector or sector line that are not used
+            "ALTOWNER" | "GUEST" | "ACTIVE" | "DEPAPT" | "ARRAPT" | "DISPLAY" => return Ok(()),
+            _ => {}
+        }
+
+        self.current_airspace_block = AirspaceBlock::None;
+        match keyword.as_str() {
+            "COPX" => self.parse_copx_line(value, CoordinationPointType::Copx),
+            "FIR_COPX" => self.parse_copx_line(value, CoordinationPointType::FirCopx),
+            "MSAW" => self.parse_msaw_line(value),
+            "SECTORLINE" => self.parse_sectorline_line(value),
+            "CIRCLE_SECTORLINE" => self.parse_circle_sectorline_line(value),
+            "SECTOR" => self.parse_sector_line(value),
+            _ => Ok(()),
+        }
+    }
+
+    fn parse_airspace_coord_line(&mut self, value: &str) -> SectorResult<()> {
+        let mut sections = value.split(':').skip(1);
+        let lat = sections.next().ok_or(Error::InvalidPosition)?;
+        let lon = sections.next().ok_or(Error::InvalidPosition)?;
+        match self.current_airspace_block {
+            AirspaceBlock::Msaw => {
+                let position = self.position_creator.try_new_from_es(lat, lon)?.validate()?;
+                self.msaw_areas.last_mut().ok_or(Error::InvalidMsaw)?.vertices.push(position);
+            }
+            AirspaceBlock::SectorLine => {
+                let position = self.position_creator.try_new_from_es(lat, lon)?.validate()?;
+                self.sector_lines.last_mut().ok_or(Error::InvalidSectorLine)?.points.push(position);
+            }
+            AirspaceBlock::Sector => {}
+            AirspaceBlock::None => {}
+        }
+        Ok(())
+    }
+
+    fn parse_msaw_line(&mut self, value: &str) -> SectorResult<()> {
+        let mut sections = value.split(':').skip(1);
+        let name = sections.next().ok_or(Error::InvalidMsaw)?;
+        let altitude = sections
+            .next()
+            .and_then(|altitude| altitude.trim().parse::<u32>().ok())
+            .ok_or(Error::InvalidMsaw)?;
+
+        self.msaw_areas.push(MsawArea {
+            name: name.to_owned(),
+            altitude,
+            vertices: Vec::new(),
+        });
+        self.current_airspace_block = AirspaceBlock::Msaw;
+        Ok(())
+    }
+
+    fn parse_sectorline_line(&mut self, value: &str) -> SectorResult<()> {
+        let name = value.split(':').nth(1).ok_or(Error::InvalidSectorLine)?;
+        self.sector_lines.push(SectorLine {
+            name: name.trim().to_owned(),
+            points: Vec::new(),
+        });
+        self.current_airspace_block = AirspaceBlock::SectorLine;
+        Ok(())
+    }
+
+    /// `CIRCLE_SECTORLINE:name:lat:lon:radius`, with the radius in nautical miles.
+    fn parse_circle_sectorline_line(&mut self, value: &str) -> SectorResult<()> {
+        let sections = value.split(':').map(str::trim).collect::<Vec<_>>();
+        if sections.len() < 5 {
+            return Err(Error::InvalidSectorLine);
+        }
+        let centre = self.position_creator.try_new_from_es(sections[2], sections[3])?.validate()?;
+        let radius = sections[4].parse::<f64>().map_err(|_| Error::InvalidSectorLine)?;
+
+        let points = (0..36)
+            .map(|i| {
+                let mut point = GeoPoint::from_degs_and_ft(centre.lat, centre.lon, 0_f64);
+                point.move_by(Bearing::from_degrees(f64::from(i * 10)), Length::from_nautical_miles(radius));
+                Position::new(point.lat.as_degrees(), point.lon.as_degrees()).validate()
+            })
+            .collect::<SectorResult<Vec<_>>>()?;
+        self.sector_lines.push(SectorLine {
+            name: sections[1].to_owned(),
+            points,
+        });
+        Ok(())
+    }
+
+    fn parse_sector_line(&mut self, value: &str) -> SectorResult<()> {
+        let sections = value.split(':').map(str::trim).collect::<Vec<_>>();
+        if sections.len() < 4 {
+            return Err(Error::InvalidSector);
+        }
+        let bottom = sections[2].parse::<u32>().map_err(|_| Error::InvalidSector)?;
+        let top = sections[3].parse::<u32>().map_err(|_| Error::InvalidSector)?;
+        self.sectors.push(AirspaceSector {
+            name: sections[1].to_owned(),
+            bottom,
+            top,
+            owners: Vec::new(),
+            border: Vec::new(),
+        });
+        self.current_airspace_block = AirspaceBlock::Sector;
+        Ok(())
+    }
+
+    fn parse_sector_owner_line(&mut self, value: &str) -> SectorResult<()> {
+        let sector = self.current_sector()?;
+        sector.owners.extend(value.split(':').skip(1).map(str::trim).filter(|owner| !owner.is_empty()).map(str::to_owned));
+        Ok(())
+    }
+
+    fn parse_sector_border_line(&mut self, value: &str) -> SectorResult<()> {
+        let sector = self.current_sector()?;
+        sector.border.extend(value.split(':').skip(1).map(str::trim).filter(|line| !line.is_empty()).map(str::to_owned));
+        Ok(())
+    }
+
+    fn current_sector(&mut self) -> SectorResult<&mut AirspaceSector> {
+        match self.current_airspace_block {
+            AirspaceBlock::Sector => self.sectors.last_mut().ok_or(Error::InvalidSector),
+            _ => Err(Error::InvalidSector),
+        }
+    }
+
+    fn parse_copx_line(&mut self, value: &str, point_type: CoordinationPointType) -> SectorResult<()> {
+        let sections = value.split(':').map(str::trim).collect::<Vec<_>>();
+        if sections.len() < 13 {
+            return Err(Error::InvalidCoordinationPoint);
+        }
+        let wildcard = |section: &str| if section.is_empty() || section == "*" { None } else { Some(section.to_owned()) };
+        let level = |section: &str| -> SectorResult<Option<u32>> {
+            match wildcard(section) {
+                Some(level) => level.parse().map(Some).map_err(|_| Error::InvalidCoordinationPoint),
+                None => Ok(None),
+            }
+        };
+        let runway = |section: &str| -> SectorResult<Option<RunwayIdentifier>> {
+            match wildcard(section) {
+                Some(runway) => Ok(Some(RunwayIdentifier::from_str(&runway)?)),
+                None => Ok(None),
+            }
+        };
+
+        let fix = wildcard(sections[4]).ok_or(Error::InvalidCoordinationPoint)?;
+        let from_sector = wildcard(sections[8]).ok_or(Error::InvalidCoordinationPoint)?;
+        let to_sector = wildcard(sections[9]).ok_or(Error::InvalidCoordinationPoint)?;
+
+        let coordination_point = CoordinationPoint {
+            point_type,
+            departure_airport: wildcard(sections[1]),
+            departure_runway: runway(sections[2])?,
+            previous_fix: wildcard(sections[3]),
+            fix,
+            next_fix: wildcard(sections[5]),
+            arrival_airport: wildcard(sections[6]),
+            arrival_runway: runway(sections[7])?,
+            from_sector,
+            to_sector,
+            climb_level: level(sections[10])?,
+            descent_level: level(sections[11])?,
+            name: sections[12].to_owned(),
+        };
+        self.coordination_points.push(coordination_point);
+        Ok(())
+    }
+
+    pub fn parse_radar_line(&mut self, value: &str) -> SectorResult<()> {
+        let sections = value.split(':').map(str::trim).collect::<Vec<_>>();
+        if sections.len() < 5 || !sections[0].eq_ignore_ascii_case("RADAR") || sections[1].is_empty() {
+            return Err(Error::InvalidRadar);
+        }
+        let position = self.position_creator.try_new_from_es(sections[2], sections[3])?.validate()?;
+        let height = sections[4].parse::<f64>().map_err(|_| Error::InvalidRadar)?.round() as u32;
+
+        // Each mode is a (range, altitude, cone slope) triple following the height
+        let mut modes = sections[5..].chunks(3).map(|mode| -> SectorResult<Option<RadarCoverage>> {
+            if mode.len() < 3 || mode.iter().all(|section| section.is_empty()) {
+                return Ok(None);
+            }
+            let range = mode[0].parse::<u32>().map_err(|_| Error::InvalidRadar)?;
+            let altitude = mode[1].parse::<u32>().map_err(|_| Error::InvalidRadar)?;
+            let cone_slope = mode[2].parse::<f64>().map_err(|_| Error::InvalidRadar)?;
+            Ok((range > 0).then_some(RadarCoverage { range, altitude, cone_slope }))
+        });
+        let primary = modes.next().transpose()?.flatten();
+        let mode_s = modes.next().transpose()?.flatten();
+        let secondary = modes.next().transpose()?.flatten();
+
+        self.radar_sites.push(RadarSite {
+            name: sections[1].to_owned(),
+            position,
+            height,
+            primary,
+            mode_s,
+            secondary,
+        });
+        Ok(())
+    }
 }
\ No newline at end of file
@@ -0,0 +1,105 @@
+use std::fmt::Display;
+
+use crate::loaders::euroscope::{
+    position::{Position, Valid},
+    sector::Sector,
+};
+
+use super::{Ese, ProcedureType, RunwayIdentifier};
+
+/// An ESE SID or STAR with its route resolved to coordinates.
+///
+/// SIDs start at the threshold of their runway and STARs end at it, when the runway is found in the sector file.
+#[derive(Debug, Clone)]
+pub struct ResolvedProcedure {
+    pub airport: String,
+    pub runway: RunwayIdentifier,
+    pub proc_type: ProcedureType,
+    pub identifier: String,
+    pub points: Vec<(String, Position<Valid>)>,
+}
+
+/// A route element of a procedure that could not be found in the sector file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnresolvedWaypoint {
+    pub airport: String,
+    pub runway: RunwayIdentifier,
+    pub procedure: String,
+    pub waypoint: String,
+}
+impl Display for UnresolvedWaypoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Unknown waypoint {} in {} ({} {})", self.waypoint, self.procedure, self.airport, self.runway)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ProcedureResolution {
+    pub procedures: Vec<ResolvedProcedure>,
+    pub unresolved: Vec<UnresolvedWaypoint>,
+}
+
+/// Joins the procedures of an [`Ese`] with the navaids, fixes and runways of the matching [`Sector`].
+pub struct ProcedureResolver<'a> {
+    sector: &'a Sector,
+    ese: &'a Ese,
+}
+
+impl<'a> ProcedureResolver<'a> {
+    pub fn new(sector: &'a Sector, ese: &'a Ese) -> Self {
+        Self { sector, ese }
+    }
+
+    pub fn resolve(&self) -> ProcedureResolution {
+        let mut resolution = ProcedureResolution::default();
+
+        for airport in &self.ese.sids_stars {
+            let sct_airport = self
+                .sector
+                .airports
+                .iter()
+                .find(|sct_airport| sct_airport.identifier.eq_ignore_ascii_case(&airport.identifier));
+
+            for (runway, procedures) in &airport.runways {
+                let threshold = sct_airport
+                    .and_then(|sct_airport| sct_airport.runway_end(runway.number(), &runway.modifier()))
+                    .map(|runway_end| (format!("RW{}", runway), runway_end.td_threshold_pos));
+
+                for procedure in procedures {
+                    let mut unresolved = |waypoint: String| {
+                        resolution.unresolved.push(UnresolvedWaypoint {
+                            airport: airport.identifier.to_string(),
+                            runway: runway.clone(),
+                            procedure: procedure.identifier.to_string(),
+                            waypoint,
+                        })
+                    };
+
+                    let mut points = Vec::with_capacity(procedure.route.len() + 1);
+                    for waypoint in procedure.waypoints() {
+                        match self.sector.find_waypoint(waypoint) {
+                            Some(position) => points.push((waypoint.to_string(), position)),
+                            None => unresolved(waypoint.to_string()),
+                        }
+                    }
+
+                    match (&threshold, procedure.proc_type) {
+                        (Some(threshold), ProcedureType::SID) => points.insert(0, threshold.clone()),
+                        (Some(threshold), ProcedureType::STAR) => points.push(threshold.clone()),
+                        (None, _) => unresolved(format!("RW{}", runway)),
+                    }
+
+                    resolution.procedures.push(ResolvedProcedure {
+                        airport: airport.identifier.to_string(),
+                        runway: runway.clone(),
+                        proc_type: procedure.proc_type,
+                        identifier: procedure.identifier.to_string(),
+                        points,
+                    });
+                }
+            }
+        }
+
+        resolution
+    }
+}
@@ -0,0 +1,38 @@
+use crate::loaders::euroscope::position::{Position, Valid};
+
+/// A radar site from the [RADAR] section.
+///
+/// Lines have the form
+/// `RADAR:name:lat:lon:height:P range:P altitude:P cone slope:S range:S altitude:S cone slope:C range:C altitude:C cone slope`,
+/// where P is the primary radar, S the Mode-S and C the Mode-A/C secondary radar.
+/// Modes that are missing or have a zero range are not fitted to the site.
+#[derive(Debug, Clone)]
+pub struct RadarSite {
+    pub name: String,
+    pub position: Position<Valid>,
+    /// Antenna height in feet above mean sea level.
+    pub height: u32,
+    pub primary: Option<RadarCoverage>,
+    pub mode_s: Option<RadarCoverage>,
+    pub secondary: Option<RadarCoverage>,
+}
+impl RadarSite {
+    /// The largest range of any mode fitted to this site, in nautical miles.
+    pub fn max_range(&self) -> Option<u32> {
+        [&self.primary, &self.mode_s, &self.secondary]
+            .into_iter()
+            .flatten()
+            .map(|coverage| coverage.range)
+            .max()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RadarCoverage {
+    /// Maximum range in nautical miles.
+    pub range: u32,
+    /// Maximum altitude covered, in feet.
+    pub altitude: u32,
+    /// Slope of the cone of silence above the antenna, in degrees from the horizontal.
+    pub cone_slope: f64,
+}
@@ -87,8 +87,8 @@ impl<R: BufRead> EseReader<R> {
                     FileSection::FreeText => self.partial_ese.parse_freetext_line(line),
                     FileSection::SidsStars => self.partial_ese.parse_sids_stars_line(line),
                     FileSection::Positions => self.partial_ese.parse_atc_position_line(line),
-                    // FileSection::Airspace => todo!(),
-                    // FileSection::Radar => todo!(),
+                    FileSection::Airspace => self.partial_ese.parse_airspace_line(line),
+                    FileSection::Radar => self.partial_ese.parse_radar_line(line),
                     // FileSection::Ground => todo!(),
                     _ => continue,
                 };
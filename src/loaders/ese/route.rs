@@ -0,0 +1,373 @@
+use aviation_calc_util::geo::GeoPoint;
+
+use crate::loaders::euroscope::{
+    navdata::NavData,
+    position::{Position, Valid},
+    sector::Sector,
+};
+
+use super::{airspace::{polygon_contains, AirspaceSector}, is_speed_level_group, Ese, Procedure, ProcedureType, RunwayIdentifier};
+
+/// Distance between the points at which a leg is checked for the sectors it crosses.
+const SECTOR_SAMPLE_NM: f64 = 2.0;
+
+#[derive(Debug, Clone)]
+pub struct RouteWaypoint {
+    pub name: String,
+    pub position: Position<Valid>,
+    /// The airway or procedure the waypoint was reached by, `None` for a direct leg.
+    pub via: Option<String>,
+    /// The filed level in feet from the last speed / level group.
+    pub level: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnexpandedReason {
+    UnknownWaypoint,
+    /// The airway does not join the waypoints either side of it.
+    AirwayNotConnected,
+    /// The SID or STAR has a different route for each runway and no runway was given.
+    AmbiguousProcedure,
+}
+
+/// A route element that could not be expanded. It is skipped, so the route continues direct.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnexpandedElement {
+    pub element: String,
+    pub reason: UnexpandedReason,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ExpandedRoute {
+    pub waypoints: Vec<RouteWaypoint>,
+    /// The ESE sectors the route passes through, in order.
+    pub sectors: Vec<String>,
+    pub unexpanded: Vec<UnexpandedElement>,
+}
+
+/// Expands ICAO route strings into waypoints with coordinates.
+///
+/// Waypoints are looked up in the sector file first, then in the NavData intersections and airway fixes.
+/// Airways are expanded from the NavData airway graph and SIDs / STARs from the ESE.
+#[derive(Default)]
+pub struct RouteExpander<'a> {
+    sector: Option<&'a Sector>,
+    ese: Option<&'a Ese>,
+    navdata: Option<&'a NavData>,
+}
+
+impl<'a> RouteExpander<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_sector(mut self, sector: &'a Sector) -> Self {
+        self.sector = Some(sector);
+        self
+    }
+
+    pub fn with_ese(mut self, ese: &'a Ese) -> Self {
+        self.ese = Some(ese);
+        self
+    }
+
+    pub fn with_navdata(mut self, navdata: &'a NavData) -> Self {
+        self.navdata = Some(navdata);
+        self
+    }
+
+    /// Expands `route` between the optional departure and arrival airports.
+    ///
+    /// A SID is only recognised as the first route element and a STAR as the last, and only when the airport is given.
+    /// Without a runway, a procedure is only expanded if its route is the same for all of its runways.
+    pub fn expand(&self, departure: Option<&str>, route: &str, arrival: Option<&str>) -> ExpandedRoute {
+        self.expand_for_runways(departure, None, route, arrival, None)
+    }
+
+    /// Expands `route` like [`Self::expand`], using the SID of `departure_runway` and the STAR of `arrival_runway`.
+    pub fn expand_for_runways(
+        &self,
+        departure: Option<&str>,
+        departure_runway: Option<&RunwayIdentifier>,
+        route: &str,
+        arrival: Option<&str>,
+        arrival_runway: Option<&RunwayIdentifier>,
+    ) -> ExpandedRoute {
+        let mut expanded = ExpandedRoute::default();
+        let mut level = None;
+
+        if let Some(departure) = departure {
+            self.push_waypoint(&mut expanded, departure, None, None);
+        }
+
+        let elements = route.split_whitespace().collect::<Vec<_>>();
+        let last_index = elements.len().saturating_sub(1);
+        let mut first_point = true;
+        let mut i = 0;
+        while i < elements.len() {
+            let index = i;
+            let (name, suffix) = elements[index].split_once('/').unwrap_or((elements[index], ""));
+            if let Some(new_level) = parse_level(suffix) {
+                level = Some(new_level);
+            }
+            i += 1;
+
+            if name == "DCT" || name.is_empty() {
+                continue;
+            }
+            if is_speed_level_group(name) {
+                level = parse_level(name);
+                continue;
+            }
+            let is_first_point = std::mem::replace(&mut first_point, false);
+            if is_first_point && self.push_procedure(&mut expanded, departure, departure_runway, name, ProcedureType::SID, level) {
+                continue;
+            }
+            if index == last_index && self.push_procedure(&mut expanded, arrival, arrival_runway, name, ProcedureType::STAR, level) {
+                continue;
+            }
+            if let Some(position) = parse_coordinate(name) {
+                expanded.waypoints.push(RouteWaypoint {
+                    name: name.to_string(),
+                    position,
+                    via: None,
+                    level,
+                });
+                continue;
+            }
+
+            // An airway between two waypoints
+            if let (Some(previous), Some(next)) = (expanded.waypoints.last(), elements.get(i)) {
+                let (next, next_suffix) = next.split_once('/').unwrap_or((next, ""));
+                if self.is_airway(name) {
+                    match self.navdata.and_then(|navdata| navdata.airways.expand_airway(name, &previous.name, next)) {
+                        Some(airway) => {
+                            for fix in airway.fixes.into_iter().skip(1) {
+                                expanded.waypoints.push(RouteWaypoint {
+                                    name: fix.identifier,
+                                    position: fix.position,
+                                    via: Some(name.to_string()),
+                                    level,
+                                });
+                            }
+                            i += 1;
+                            if let Some(new_level) = parse_level(next_suffix) {
+                                level = Some(new_level);
+                            }
+                        }
+                        None => expanded.unexpanded.push(UnexpandedElement {
+                            element: format!("{} {} {}", previous.name, name, next),
+                            reason: UnexpandedReason::AirwayNotConnected,
+                        }),
+                    }
+                    continue;
+                }
+            }
+
+            self.push_waypoint(&mut expanded, name, None, level);
+        }
+
+        if let Some(arrival) = arrival {
+            self.push_waypoint(&mut expanded, arrival, None, level);
+        }
+
+        expanded.sectors = self.sectors_crossed(&expanded.waypoints);
+        expanded
+    }
+
+    /// Adds waypoint `name`, unless the route is already there, e.g. at the end of a SID.
+    fn push_waypoint(&self, expanded: &mut ExpandedRoute, name: &str, via: Option<&str>, level: Option<u32>) {
+        if expanded.waypoints.last().is_some_and(|waypoint| waypoint.name.eq_ignore_ascii_case(name)) {
+            return;
+        }
+        let near = expanded.waypoints.last().map(|waypoint| waypoint.position);
+        match self.find_waypoint(name, near) {
+            Some(position) => expanded.waypoints.push(RouteWaypoint {
+                name: name.to_string(),
+                position,
+                via: via.map(str::to_string),
+                level,
+            }),
+            None => expanded.unexpanded.push(UnexpandedElement {
+                element: name.to_string(),
+                reason: UnexpandedReason::UnknownWaypoint,
+            }),
+        }
+    }
+
+    /// Adds the waypoints of the SID or STAR `name` at `airport`, returning `false` if there is no such procedure.
+    fn push_procedure(
+        &self,
+        expanded: &mut ExpandedRoute,
+        airport: Option<&str>,
+        runway: Option<&RunwayIdentifier>,
+        name: &str,
+        proc_type: ProcedureType,
+        level: Option<u32>,
+    ) -> bool {
+        let Some(airport) = self.ese.zip(airport).and_then(|(ese, airport)| ese.airport(airport)) else {
+            return false;
+        };
+        let runways = match runway {
+            Some(runway) => vec![runway],
+            None => airport.runways_for_procedure(name, proc_type),
+        };
+        let procedures = runways
+            .into_iter()
+            .filter_map(|runway| {
+                airport.procedures(runway, proc_type).into_iter().find(|procedure| procedure.identifier.eq_ignore_ascii_case(name))
+            })
+            .collect::<Vec<_>>();
+        let Some(procedure) = procedures.first() else {
+            return false;
+        };
+        let same_route = |other: &&Procedure| procedure.waypoints().eq(other.waypoints());
+        if !procedures.iter().all(same_route) {
+            expanded.unexpanded.push(UnexpandedElement {
+                element: name.to_string(),
+                reason: UnexpandedReason::AmbiguousProcedure,
+            });
+            return true;
+        }
+        for waypoint in procedure.waypoints() {
+            self.push_waypoint(expanded, waypoint, Some(&procedure.identifier), level);
+        }
+        true
+    }
+
+    fn is_airway(&self, name: &str) -> bool {
+        self.navdata.is_some_and(|navdata| navdata.airways.has_airway(name))
+    }
+
+    fn find_waypoint(&self, name: &str, near: Option<Position<Valid>>) -> Option<Position<Valid>> {
+        if let Some(sector) = self.sector {
+            // Airport identifiers are not always upper case in sector files
+            let airport = || {
+                sector.airports.iter().find(|airport| airport.identifier.eq_ignore_ascii_case(name)).map(|airport| airport.position)
+            };
+            if let Some(position) = sector.find_waypoint(name).or_else(airport) {
+                return Some(position);
+            }
+        }
+        let navdata = self.navdata?;
+        navdata.intersections.find_nearest(name, near).or_else(|| {
+            let fixes = navdata.airways.find_fixes(name);
+            let mut positions = fixes.iter().map(|index| navdata.airways.fixes()[*index].position);
+            match near {
+                Some(near) => positions.min_by(|a, b| distance_nm(*a, near).total_cmp(&distance_nm(*b, near))),
+                None => positions.next(),
+            }
+        })
+    }
+
+    /// The ESE sectors along the route. Where sectors overlap, the one with the smallest vertical extent is used.
+    fn sectors_crossed(&self, waypoints: &[RouteWaypoint]) -> Vec<String> {
+        let Some(ese) = self.ese else {
+            return Vec::new();
+        };
+        let polygons = ese
+            .sectors
+            .iter()
+            .map(|sector| (sector, ese.sector_polygon(sector)))
+            .collect::<Vec<_>>();
+        let sector_at = |position: Position<Valid>, level: Option<u32>| -> Option<&AirspaceSector> {
+            polygons
+                .iter()
+                .filter(|(sector, polygon)| {
+                    level.is_none_or(|level| sector.contains_level(level)) && polygon_contains(polygon, position)
+                })
+                .min_by_key(|(sector, _)| sector.top.saturating_sub(sector.bottom))
+                .map(|(sector, _)| *sector)
+        };
+
+        let mut sectors: Vec<String> = Vec::new();
+        let mut push = |sector: Option<&AirspaceSector>| {
+            if let Some(sector) = sector {
+                if sectors.last() != Some(&sector.name) {
+                    sectors.push(sector.name.to_string());
+                }
+            }
+        };
+
+        if let Some(first) = waypoints.first() {
+            push(sector_at(first.position, first.level));
+        }
+        for leg in waypoints.windows(2) {
+            let (start, end) = (leg[0].position, leg[1].position);
+            let level = leg[1].level;
+            let samples = (distance_nm(start, end) / SECTOR_SAMPLE_NM).ceil().max(1.0) as usize;
+            for step in 1..=samples {
+                let fraction = step as f64 / samples as f64;
+                let lat = start.lat + (end.lat - start.lat) * fraction;
+                let lon = start.lon + (end.lon - start.lon) * fraction;
+                if let Ok(position) = Position::new(lat, lon).validate() {
+                    push(sector_at(position, level));
+                }
+            }
+        }
+        sectors
+    }
+}
+
+/// The level in feet of a speed / level group such as `N0450F350` or `M082S1130`, or of a bare level such as `F350`.
+fn parse_level(value: &str) -> Option<u32> {
+    let level = match value.chars().next()? {
+        'N' | 'K' if value.len() > 5 => value.get(5..)?,
+        'M' if value.len() > 4 && !value.get(1..)?.starts_with(|c: char| c.is_ascii_alphabetic()) => value.get(4..)?,
+        _ => value,
+    };
+    let number = level.get(1..)?.parse::<u32>().ok()?;
+    match level.chars().next()? {
+        'F' | 'A' => number.checked_mul(100),
+        // Tens of metres
+        'S' | 'M' => Some((f64::from(number) * 10.0 * 3.28084).round() as u32),
+        _ => None,
+    }
+}
+
+/// An ICAO coordinate waypoint, either degrees (`51N010W`) or degrees and minutes (`5130N00010W`).
+fn parse_coordinate(value: &str) -> Option<Position<Valid>> {
+    // Route elements come from flight plans, so the slicing below must not split a character
+    if !value.is_ascii() {
+        return None;
+    }
+    let bytes = value.as_bytes();
+    let (lat_len, lon_len) = match value.len() {
+        7 => (2, 3),
+        11 => (4, 5),
+        _ => return None,
+    };
+    let lat_hemisphere = bytes[lat_len] as char;
+    let lon_hemisphere = *bytes.last()? as char;
+    let lat = parse_degrees_minutes(&value[..lat_len], 2)?;
+    let lon = parse_degrees_minutes(&value[lat_len + 1..lat_len + 1 + lon_len], 3)?;
+    let lat = match lat_hemisphere {
+        'N' => lat,
+        'S' => -lat,
+        _ => return None,
+    };
+    let lon = match lon_hemisphere {
+        'E' => lon,
+        'W' => -lon,
+        _ => return None,
+    };
+    Position::new(lat, lon).validate().ok()
+}
+
+fn parse_degrees_minutes(value: &str, degree_digits: usize) -> Option<f64> {
+    if !value.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let degrees = value[..degree_digits].parse::<f64>().ok()?;
+    let minutes = match value.get(degree_digits..) {
+        Some("") | None => 0.0,
+        Some(minutes) => minutes.parse::<f64>().ok()?,
+    };
+    Some(degrees + minutes / 60.0)
+}
+
+fn distance_nm(a: Position<Valid>, b: Position<Valid>) -> f64 {
+    GeoPoint::from_degs_and_ft(a.lat, a.lon, 0.0)
+        .flat_distance(&GeoPoint::from_degs_and_ft(b.lat, b.lon, 0.0))
+        .as_nautical_miles()
+}
@@ -0,0 +1,194 @@
+use std::{collections::HashMap, io::BufRead};
+
+/// One `.command text` line of an alias file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alias {
+    /// The command without its leading `.`, e.g. `ils`.
+    pub command: String,
+    pub text: String,
+    pub line: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AliasFile {
+    pub file_name: String,
+    pub aliases: Vec<Alias>,
+}
+
+impl AliasFile {
+    /// Parses an alias file. Comment lines start with `;` and any line that does not start with `.` is ignored.
+    pub fn try_from_reader(reader: impl BufRead, file_name: &str) -> anyhow::Result<Self> {
+        let mut ret_val = AliasFile {
+            file_name: file_name.to_string(),
+            ..Default::default()
+        };
+
+        for (line_number, line) in reader.split(b'\n').enumerate() {
+            let line = String::from_utf8_lossy(&line?).to_string();
+            let Some(line) = line.trim().strip_prefix('.') else {
+                continue;
+            };
+            let (command, text) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            if command.is_empty() {
+                continue;
+            }
+            ret_val.aliases.push(Alias {
+                command: command.to_string(),
+                text: text.trim().to_string(),
+                line: line_number + 1,
+            });
+        }
+
+        Ok(ret_val)
+    }
+
+    /// The alias for `command`, with or without its leading `.` (case-insensitive). Later definitions win.
+    pub fn get(&self, command: &str) -> Option<&Alias> {
+        let command = command.strip_prefix('.').unwrap_or(command);
+        self.aliases.iter().rev().find(|alias| alias.command.eq_ignore_ascii_case(command))
+    }
+}
+
+/// Supplies the values of alias variables such as `$dep` or `$arrrwy(EGLL)`.
+pub trait AliasContext {
+    /// The value of variable `name` (lower case, without `$`), called with `args` if it was written as a function.
+    fn resolve(&self, name: &str, args: &[String]) -> Option<String>;
+}
+
+/// Variables are looked up by name. Functions are looked up as `name(arg1,arg2)` first, then by name alone.
+impl AliasContext for HashMap<String, String> {
+    fn resolve(&self, name: &str, args: &[String]) -> Option<String> {
+        if !args.is_empty() {
+            if let Some(value) = self.get(&format!("{}({})", name, args.join(","))) {
+                return Some(value.to_string());
+            }
+        }
+        self.get(name).cloned()
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AliasExpansion {
+    pub text: String,
+    /// Variables the context had no value for. They are left in the text as written.
+    pub unresolved: Vec<String>,
+}
+
+/// Expands aliases and their variables.
+///
+/// `$1` to `$9` are the arguments typed after the command. All other variables are taken from an [`AliasContext`].
+pub struct AliasExpander<'a> {
+    aliases: &'a AliasFile,
+}
+
+impl<'a> AliasExpander<'a> {
+    pub fn new(aliases: &'a AliasFile) -> Self {
+        Self { aliases }
+    }
+
+    /// Expands a typed command such as `.ils 27R`. Returns `None` if there is no alias for the command.
+    pub fn expand_command(&self, input: &str, context: &dyn AliasContext) -> Option<AliasExpansion> {
+        let mut items = input.split_whitespace();
+        let alias = self.aliases.get(items.next()?)?;
+        let args = items.collect::<Vec<_>>();
+        Some(Self::expand_text(&alias.text, &args, context))
+    }
+
+    pub fn expand_text(text: &str, args: &[&str], context: &dyn AliasContext) -> AliasExpansion {
+        let mut unresolved = Vec::new();
+        let text = Self::expand_into(text, args, context, &mut unresolved);
+        AliasExpansion { text, unresolved }
+    }
+
+    fn expand_into(text: &str, args: &[&str], context: &dyn AliasContext, unresolved: &mut Vec<String>) -> String {
+        let mut ret_val = String::with_capacity(text.len());
+        let mut rest = text;
+
+        while let Some(start) = rest.find('$') {
+            ret_val.push_str(&rest[..start]);
+            let after = &rest[start + 1..];
+            let name_len = after
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(after.len());
+            if name_len == 0 {
+                ret_val.push('$');
+                rest = after;
+                continue;
+            }
+            let name = &after[..name_len];
+            let mut end = name_len;
+
+            // Function arguments, which may contain variables themselves
+            let mut fn_args = Vec::new();
+            if after[name_len..].starts_with('(') {
+                if let Some(close) = Self::closing_bracket(&after[name_len..]) {
+                    let inner = &after[name_len + 1..name_len + close];
+                    fn_args = Self::split_args(inner)
+                        .into_iter()
+                        .map(|arg| Self::expand_into(arg.trim(), args, context, unresolved))
+                        .collect();
+                    end = name_len + close + 1;
+                }
+            }
+
+            let written = &after[..end];
+            let value = match name.parse::<usize>() {
+                Ok(index) => index.checked_sub(1).and_then(|index| args.get(index)).map(|arg| arg.to_string()),
+                Err(_) => context.resolve(&name.to_lowercase(), &fn_args),
+            };
+            match value {
+                Some(value) => ret_val.push_str(&value),
+                None => {
+                    unresolved.push(written.to_string());
+                    ret_val.push('$');
+                    ret_val.push_str(written);
+                }
+            }
+            rest = &after[end..];
+        }
+
+        ret_val.push_str(rest);
+        ret_val
+    }
+
+    /// The index of the `)` matching the `(` at the start of `text`.
+    fn closing_bracket(text: &str) -> Option<usize> {
+        let mut depth = 0;
+        for (i, c) in text.char_indices() {
+            match c {
+                '(' => depth += 1,
+                ')' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Some(i);
+                    }
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+
+    /// Splits function arguments on the commas that are not inside nested brackets.
+    fn split_args(text: &str) -> Vec<&str> {
+        if text.trim().is_empty() {
+            return Vec::new();
+        }
+        let mut ret_val = Vec::new();
+        let mut depth = 0;
+        let mut start = 0;
+        for (i, c) in text.char_indices() {
+            match c {
+                '(' => depth += 1,
+                ')' => depth -= 1,
+                ',' if depth == 0 => {
+                    ret_val.push(&text[start..i]);
+                    start = i + 1;
+                }
+                _ => {}
+            }
+        }
+        ret_val.push(&text[start..]);
+        ret_val
+    }
+}
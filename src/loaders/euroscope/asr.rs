@@ -1,8 +1,8 @@
-use std::{fs::File, io::{BufRead, BufReader}, path::Path};
+use std::{fs::File, io::{BufRead, BufReader}, path::Path, str::FromStr};
 
 use aviation_calc_util::{geo::GeoPoint, units::Angle};
 
-use super::symbology::SymbologyItemType;
+use super::{error::Error, symbology::SymbologyItemType, SectorResult};
 
 
 #[derive(Debug, Clone, Default)]
@@ -15,6 +15,8 @@ pub struct EsAsr {
     pub sector_file_id: Option<String>,
     pub sector_title: String,
     pub display_items: Vec<DisplayItem>,
+    /// `PLUGIN:plugin:key:value` lines, which plugins such as TopSky use to keep their own settings.
+    pub plugin_items: Vec<PluginItem>,
     pub show_c: bool,
     pub shows_b: bool,
     pub below: i32,
@@ -27,56 +29,94 @@ pub struct EsAsr {
     pub disable_panning: bool,
     pub disable_zooming: bool,
     pub display_rotation: Angle,
-    pub window_area: (GeoPoint, GeoPoint)
+    pub window_area: (GeoPoint, GeoPoint),
+    pub non_critical_errors: Vec<(usize, String, Error)>
 }
 
 impl EsAsr {
     pub fn try_from_asr_file(asr_file: impl AsRef<Path>) -> anyhow::Result<(Self, String)> {
         let file_reader = BufReader::new(File::open(&asr_file)?);
+        Self::try_from_reader(file_reader, asr_file)
+    }
+
+    /// Parses an ASR from any reader. `asr_file` is only recorded as the file name.
+    ///
+    /// Lines that cannot be parsed are skipped and kept in `non_critical_errors`.
+    pub fn try_from_reader(file_reader: impl BufRead, asr_file: impl AsRef<Path>) -> anyhow::Result<(Self, String)> {
         let mut ret_val = Self::default();
         let mut sector_file = "".to_string();
         ret_val.file_name = asr_file.as_ref().to_str().unwrap().to_string();
 
-        for line in file_reader.lines() {
-            if let Ok(line_str) = line {
-                let items = line_str.split(":").collect::<Vec<&str>>();
-
-                if items.len() > 0 {
-                    match items[0].to_lowercase().as_str() {
-                        "displaytypename" => ret_val.display_type_name = items[1].to_string(),
-                        "displaytypeneedradarcontent" => ret_val.display_type_need_radar_content = items[1].parse::<u8>()? != 0,
-                        "displaytypegeoreferenced" => ret_val.display_type_geo_reference = items[1].parse::<u8>()? != 0,
-                        "sectorfile" => sector_file = items[1].to_string(),
-                        "sectortitle" => ret_val.sector_title = items[1].to_string(),
-                        "showc" => ret_val.show_c = items[1].parse::<u8>()? != 0,
-                        "showsb" => ret_val.shows_b = items[1].parse::<u8>()? != 0,
-                        "below" => ret_val.below = items[1].parse()?,
-                        "above" => ret_val.above = items[1].parse()?,
-                        "leader" => ret_val.leader = items[1].parse()?,
-                        "showleader" => ret_val.show_leader = items[1].parse::<u8>()? != 0,
-                        "turnleader" => ret_val.turn_leader = items[1].parse::<u8>()? != 0,
-                        "history_dots" => ret_val.history_dots = items[1].parse()?,
-                        "simulation_mode" => ret_val.simulation_mode = items[1].parse()?,
-                        "disablepanning" => ret_val.disable_panning = items[1].parse::<u8>()? != 0,
-                        "disablezooming" => ret_val.disable_zooming = items[1].parse::<u8>()? != 0,
-                        "displayrotation" => ret_val.display_rotation = Angle::from_degrees(items[1].parse()?),
-                        "windowarea" => ret_val.window_area = (GeoPoint::from_degs_and_ft(items[1].parse()?, items[2].parse()?, 0_f64), GeoPoint::from_degs_and_ft(items[3].parse()?, items[4].parse()?, 0_f64)),
-                        &_ => {
-                            if let Ok(symbol_type) = items[0].try_into() {
-                                ret_val.display_items.push(DisplayItem {
-                                    item_type: symbol_type,
-                                    name: items[1].to_string(),
-                                    attribute: items[2].to_string()
-                                });
-                            }
-                        }
-                    }
-                }
+        for (line_number, line) in file_reader.split(b'\n').enumerate() {
+            let line = String::from_utf8_lossy(&line?).trim_end_matches('\r').to_string();
+            if line.is_empty() {
+                continue;
+            }
+            if let Err(e) = ret_val.parse_line(&line, &mut sector_file) {
+                ret_val.non_critical_errors.push((line_number + 1, line, e));
             }
         }
 
         Ok((ret_val, sector_file.to_string()))
     }
+
+    fn parse_line(&mut self, line: &str, sector_file: &mut String) -> SectorResult<()> {
+        let items = line.split(":").collect::<Vec<&str>>();
+        let text = |index: usize| items.get(index).map(|item| item.to_string()).ok_or(Error::InvalidAsrSetting);
+        let flag = |index: usize| parse_item::<u8>(&items, index).map(|flag| flag != 0);
+
+        match items[0].to_lowercase().as_str() {
+            "displaytypename" => self.display_type_name = text(1)?,
+            "displaytypeneedradarcontent" => self.display_type_need_radar_content = flag(1)?,
+            "displaytypegeoreferenced" => self.display_type_geo_reference = flag(1)?,
+            "sectorfile" => *sector_file = text(1)?,
+            "sectortitle" => self.sector_title = text(1)?,
+            "showc" => self.show_c = flag(1)?,
+            "showsb" => self.shows_b = flag(1)?,
+            "below" => self.below = parse_item(&items, 1)?,
+            "above" => self.above = parse_item(&items, 1)?,
+            "leader" => self.leader = parse_item(&items, 1)?,
+            "showleader" => self.show_leader = flag(1)?,
+            "turnleader" => self.turn_leader = flag(1)?,
+            "history_dots" => self.history_dots = parse_item(&items, 1)?,
+            "simulation_mode" => self.simulation_mode = parse_item(&items, 1)?,
+            "disablepanning" => self.disable_panning = flag(1)?,
+            "disablezooming" => self.disable_zooming = flag(1)?,
+            "displayrotation" => self.display_rotation = Angle::from_degrees(parse_item(&items, 1)?),
+            "windowarea" => {
+                self.window_area = (
+                    GeoPoint::from_degs_and_ft(parse_item(&items, 1)?, parse_item(&items, 2)?, 0_f64),
+                    GeoPoint::from_degs_and_ft(parse_item(&items, 3)?, parse_item(&items, 4)?, 0_f64),
+                )
+            }
+            "plugin" => {
+                let plugin = items.get(1).filter(|plugin| !plugin.is_empty()).ok_or(Error::InvalidAsrSetting)?;
+                self.plugin_items.push(PluginItem {
+                    plugin: plugin.to_string(),
+                    key: items.get(2).copied().unwrap_or_default().to_string(),
+                    value: items.get(3..).unwrap_or_default().join(":")
+                });
+            }
+            &_ => {
+                // Every other line with a name is an item, including item types added by newer versions
+                let name = items.get(1).ok_or(Error::InvalidAsrSetting)?;
+                let item_type = SymbologyItemType::try_from(items[0]).unwrap_or_else(|_| SymbologyItemType::Unknown(items[0].to_string()));
+                self.display_items.push(DisplayItem {
+                    item_type,
+                    name: name.to_string(),
+                    attribute: items.get(2..).unwrap_or_default().join(":")
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+fn parse_item<T: FromStr>(items: &[&str], index: usize) -> SectorResult<T> {
+    items
+        .get(index)
+        .and_then(|item| item.trim().parse().ok())
+        .ok_or(Error::InvalidAsrSetting)
 }
 
 
@@ -85,4 +125,11 @@ pub struct DisplayItem {
     pub item_type: SymbologyItemType,
     pub name: String,
     pub attribute: String
-}
\ No newline at end of file
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PluginItem {
+    pub plugin: String,
+    pub key: String,
+    pub value: String
+}
@@ -23,6 +23,20 @@ pub enum Error {
     InvalidOffset,
     InvalidFreetext,
     InvalidAtcPosition,
+    InvalidCoordinationPoint,
+    InvalidMsaw,
+    InvalidRadar,
+    InvalidAirway,
+    InvalidIntersection,
+    InvalidSectorLine,
+    InvalidSector,
+    InvalidTopSkyMap,
+    InvalidStand,
+    InvalidGroundArea,
+    InvalidTagDefinition,
+    InvalidListDefinition,
+    InvalidSymbology,
+    InvalidAsrSetting,
 }
 
 impl Display for Error {
@@ -52,6 +66,20 @@ impl Display for Error {
                 Self::InvalidOffset => "Invalid offset",
                 Self::InvalidFreetext => "Invalid freetext",
                 Self::InvalidAtcPosition => "Invalid ATC position",
+                Self::InvalidCoordinationPoint => "Invalid coordination point",
+                Self::InvalidMsaw => "Invalid MSAW area",
+                Self::InvalidRadar => "Invalid radar site",
+                Self::InvalidAirway => "Invalid airway segment",
+                Self::InvalidIntersection => "Invalid intersection",
+                Self::InvalidSectorLine => "Invalid sector line",
+                Self::InvalidSector => "Invalid sector",
+                Self::InvalidTopSkyMap => "Invalid TopSky map item",
+                Self::InvalidStand => "Invalid stand",
+                Self::InvalidGroundArea => "Invalid ground map area",
+                Self::InvalidTagDefinition => "Invalid tag definition",
+                Self::InvalidListDefinition => "Invalid list definition",
+                Self::InvalidSymbology => "Invalid symbology line",
+                Self::InvalidAsrSetting => "Invalid ASR setting",
             }
         )
     }
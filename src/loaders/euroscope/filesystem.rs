@@ -0,0 +1,224 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Debug,
+    fs::File,
+    io::{self, BufRead, BufReader, Cursor, Read, Seek},
+    path::{Component, Path, PathBuf},
+};
+
+use flate2::read::GzDecoder;
+
+/// The file operations the EuroScope loaders need, so profiles can be read from disk, memory or an archive.
+pub trait FileSystem: Debug + Send + Sync {
+    fn open(&self, path: &Path) -> io::Result<Box<dyn BufRead + '_>>;
+    fn exists(&self, path: &Path) -> bool;
+    fn is_dir(&self, path: &Path) -> bool;
+    /// The entries directly inside `path`.
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>>;
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf>;
+}
+
+/// The real filesystem, through `std::fs`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StdFileSystem;
+
+impl FileSystem for StdFileSystem {
+    fn open(&self, path: &Path) -> io::Result<Box<dyn BufRead + '_>> {
+        Ok(Box::new(BufReader::new(File::open(path)?)))
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        path.exists()
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        path.is_dir()
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        std::fs::read_dir(path)?.map(|entry| entry.map(|entry| entry.path())).collect()
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        path.canonicalize()
+    }
+}
+
+/// Files held in memory, keyed by their normalised path. Directories are implied by the files inside them.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryFileSystem {
+    files: BTreeMap<PathBuf, Vec<u8>>,
+}
+
+impl MemoryFileSystem {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, path: impl AsRef<Path>, contents: impl Into<Vec<u8>>) {
+        self.files.insert(normalise_path(path.as_ref()), contents.into());
+    }
+
+    pub fn files(&self) -> impl Iterator<Item = &PathBuf> {
+        self.files.keys()
+    }
+}
+
+impl FileSystem for MemoryFileSystem {
+    fn open(&self, path: &Path) -> io::Result<Box<dyn BufRead + '_>> {
+        let contents = self
+            .files
+            .get(&normalise_path(path))
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("{:?} not found", path)))?;
+        Ok(Box::new(Cursor::new(contents.as_slice())))
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        let path = normalise_path(path);
+        self.files.contains_key(&path) || self.is_dir(&path)
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        let path = normalise_path(path);
+        self.files.keys().any(|file| file != &path && file.starts_with(&path))
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        let path = normalise_path(path);
+        if !self.is_dir(&path) {
+            return Err(io::Error::new(io::ErrorKind::NotFound, format!("{:?} is not a directory", path)));
+        }
+        let mut entries = self
+            .files
+            .keys()
+            .filter_map(|file| file.strip_prefix(&path).ok())
+            .filter_map(|relative| relative.components().next())
+            .map(|first| path.join(first))
+            .collect::<Vec<_>>();
+        entries.dedup();
+        Ok(entries)
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        let path = normalise_path(path);
+        if self.exists(&path) {
+            Ok(path)
+        } else {
+            Err(io::Error::new(io::ErrorKind::NotFound, format!("{:?} not found", path)))
+        }
+    }
+}
+
+/// Implements [`FileSystem`] for an archive type by delegating to the [`MemoryFileSystem`] in its `inner` field.
+macro_rules! memory_backed_file_system {
+    ($archive:ty) => {
+        impl FileSystem for $archive {
+            fn open(&self, path: &Path) -> io::Result<Box<dyn BufRead + '_>> {
+                self.inner.open(path)
+            }
+
+            fn exists(&self, path: &Path) -> bool {
+                self.inner.exists(path)
+            }
+
+            fn is_dir(&self, path: &Path) -> bool {
+                self.inner.is_dir(path)
+            }
+
+            fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+                self.inner.read_dir(path)
+            }
+
+            fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+                self.inner.canonicalize(path)
+            }
+        }
+    };
+}
+
+/// The contents of a tar (or gzipped tar) archive, with the archive root mapped to `/`.
+#[derive(Debug, Clone, Default)]
+pub struct TarFileSystem {
+    inner: MemoryFileSystem,
+}
+
+impl TarFileSystem {
+    pub fn try_from_tar(reader: impl Read) -> io::Result<Self> {
+        let mut inner = MemoryFileSystem::new();
+        let mut archive = tar::Archive::new(reader);
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+            let path = Path::new("/").join(entry.path()?);
+            let mut contents = Vec::new();
+            entry.read_to_end(&mut contents)?;
+            inner.insert(path, contents);
+        }
+        Ok(TarFileSystem { inner })
+    }
+
+    pub fn try_from_tar_gz(reader: impl Read) -> io::Result<Self> {
+        Self::try_from_tar(GzDecoder::new(reader))
+    }
+
+    pub fn try_from_file(archive: impl AsRef<Path>) -> io::Result<Self> {
+        let file = BufReader::new(File::open(&archive)?);
+        match archive.as_ref().extension().and_then(|ext| ext.to_str()) {
+            Some("gz") | Some("tgz") => Self::try_from_tar_gz(file),
+            _ => Self::try_from_tar(file),
+        }
+    }
+}
+
+memory_backed_file_system!(TarFileSystem);
+
+/// The contents of a zip archive, with the archive root mapped to `/`.
+#[derive(Debug, Clone, Default)]
+pub struct ZipFileSystem {
+    inner: MemoryFileSystem,
+}
+
+impl ZipFileSystem {
+    pub fn try_from_zip(reader: impl Read + Seek) -> io::Result<Self> {
+        let mut inner = MemoryFileSystem::new();
+        let mut archive = zip::ZipArchive::new(reader)?;
+        for i in 0..archive.len() {
+            let mut entry = archive.by_index(i)?;
+            if !entry.is_file() {
+                continue;
+            }
+            // Entries that would escape the archive root are skipped
+            let Some(path) = entry.enclosed_name() else {
+                continue;
+            };
+            let path = Path::new("/").join(path);
+            let mut contents = Vec::new();
+            entry.read_to_end(&mut contents)?;
+            inner.insert(path, contents);
+        }
+        Ok(ZipFileSystem { inner })
+    }
+
+    pub fn try_from_file(archive: impl AsRef<Path>) -> io::Result<Self> {
+        Self::try_from_zip(BufReader::new(File::open(archive)?))
+    }
+}
+
+memory_backed_file_system!(ZipFileSystem);
+
+/// Resolves `.` and `..` components without touching the filesystem.
+pub fn normalise_path(path: &Path) -> PathBuf {
+    let mut normalised = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalised.pop();
+            }
+            other => normalised.push(other),
+        }
+    }
+    normalised
+}
@@ -0,0 +1,239 @@
+use std::{fmt::Display, io::BufRead};
+
+use aviation_calc_util::{
+    geo::{Bearing, GeoPoint},
+    units::Length,
+};
+
+use super::{
+    error::Error,
+    position::{Position, Valid},
+    SectorResult,
+};
+
+/// The surface an area of a ground map draws. The key strings match the ground display types of the package.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GroundAreaType {
+    Taxiway,
+    Apron,
+    Runway,
+    /// Buildings and other structures.
+    Structure,
+    Grass,
+}
+impl GroundAreaType {
+    pub fn to_key_string(&self) -> String {
+        self.to_string()
+    }
+
+    fn from_keyword(keyword: &str) -> Option<Self> {
+        match keyword.to_uppercase().as_str() {
+            "TAXIWAY" => Some(Self::Taxiway),
+            "APRON" => Some(Self::Apron),
+            "RUNWAY" => Some(Self::Runway),
+            "BUILDING" | "STRUCTURE" => Some(Self::Structure),
+            "GRASS" => Some(Self::Grass),
+            _ => None,
+        }
+    }
+}
+impl Display for GroundAreaType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Taxiway => "taxiway",
+                Self::Apron => "apron",
+                Self::Runway => "runway",
+                Self::Structure => "structure",
+                Self::Grass => "grass",
+            }
+        )
+    }
+}
+
+/// An area of a ground map, started by a `TYPE:airport:name` line and followed by its `COORD` lines.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GroundArea {
+    pub airport: String,
+    pub area_type: GroundAreaType,
+    pub name: String,
+    pub points: Vec<Position<Valid>>,
+}
+
+/// A `STAND:airport:name:lat:lon[:radius]` line and the `WTC`, `USE`, `CODE` and `COORD` lines that follow it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GroundStand {
+    pub airport: String,
+    pub name: String,
+    pub position: Position<Valid>,
+    /// The radius in metres that the stand is occupied within.
+    pub radius: Option<f64>,
+    /// The wake categories allowed on the stand, e.g. `LM`.
+    pub wake_categories: Option<String>,
+    /// The kinds of flight the stand is for, e.g. `C` for cargo.
+    pub usage: Option<String>,
+    /// The ICAO aircraft size codes allowed on the stand, e.g. `ABC`.
+    pub codes: Option<String>,
+    /// The outline of the stand, if it has one.
+    pub area: Vec<Position<Valid>>,
+}
+
+impl GroundStand {
+    /// Degrees between the points of the circle drawn for a stand without an outline.
+    const CIRCLE_STEP: usize = 30;
+
+    /// The outline of the stand, or a circle of its radius if it has none. Empty if it has neither.
+    pub fn outline(&self) -> Vec<Position<Valid>> {
+        if self.area.len() >= 3 {
+            return self.area.clone();
+        }
+        let Some(radius) = self.radius.filter(|radius| *radius > 0.0) else {
+            return Vec::new();
+        };
+        (0..=360)
+            .step_by(Self::CIRCLE_STEP)
+            .filter_map(|bearing| {
+                let mut point = GeoPoint::from_degs_and_ft(self.position.lat, self.position.lon, 0_f64);
+                point.move_by(Bearing::from_degrees(bearing as f64), Length::from_meters(radius));
+                Position::new(point.lat.as_degrees(), point.lon.as_degrees()).validate().ok()
+            })
+            .collect()
+    }
+}
+
+/// The stand and ground map files of the GRplugin and vSMR ground radar plugins.
+#[derive(Debug, Clone, Default)]
+pub struct GroundMaps {
+    pub stands: Vec<GroundStand>,
+    pub areas: Vec<GroundArea>,
+    /// The skipped lines of all the files loaded, in the order the files were loaded.
+    pub non_critical_errors: Vec<(usize, String, Error)>,
+}
+
+impl GroundMaps {
+    pub fn try_load_stands(&mut self, reader: impl BufRead) -> anyhow::Result<()> {
+        let mut in_stand = false;
+        for (line_number, line) in Self::data_lines(reader)? {
+            let sections = line.split(':').map(str::trim).collect::<Vec<_>>();
+            let result = match sections[0].to_uppercase().as_str() {
+                "STAND" => {
+                    let stand = Self::parse_stand_line(&sections);
+                    in_stand = stand.is_ok();
+                    stand.map(|stand| self.stands.push(stand))
+                }
+                keyword @ ("WTC" | "USE" | "CODE" | "COORD") if in_stand => self.parse_stand_attribute(keyword, &sections),
+                _ => Ok(()),
+            };
+            if let Err(e) = result {
+                self.non_critical_errors.push((line_number, line, e));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn try_load_maps(&mut self, reader: impl BufRead) -> anyhow::Result<()> {
+        // The line that started the current area
+        let mut area_line: Option<(usize, String)> = None;
+        for (line_number, line) in Self::data_lines(reader)? {
+            let sections = line.split(':').map(str::trim).collect::<Vec<_>>();
+            let result = if sections[0].eq_ignore_ascii_case("COORD") {
+                match self.areas.last_mut() {
+                    Some(area) if area_line.is_some() => parse_position(&sections[1..]).map(|point| area.points.push(point)),
+                    _ => Err(Error::InvalidGroundArea),
+                }
+            } else if let Some(area_type) = GroundAreaType::from_keyword(sections[0]) {
+                self.finish_area(area_line.take());
+                if sections.len() >= 2 && !sections[1].is_empty() {
+                    self.areas.push(GroundArea {
+                        airport: sections[1].to_string(),
+                        area_type,
+                        name: sections.get(2..).unwrap_or_default().join(":"),
+                        points: Vec::new(),
+                    });
+                    area_line = Some((line_number, line.to_string()));
+                    Ok(())
+                } else {
+                    Err(Error::InvalidGroundArea)
+                }
+            } else {
+                Ok(())
+            };
+            if let Err(e) = result {
+                self.non_critical_errors.push((line_number, line, e));
+            }
+        }
+        self.finish_area(area_line);
+        Ok(())
+    }
+
+    /// Removes the last area if it has too few points to be drawn.
+    fn finish_area(&mut self, area_line: Option<(usize, String)>) {
+        let Some((line_number, line)) = area_line else {
+            return;
+        };
+        if self.areas.last().is_some_and(|area| area.points.len() < 3) {
+            self.areas.pop();
+            self.non_critical_errors.push((line_number, line, Error::InvalidGroundArea));
+        }
+    }
+
+    /// The airports that have stands or ground map areas, in the order they first appear.
+    pub fn airports(&self) -> Vec<&str> {
+        let mut ret_val: Vec<&str> = Vec::new();
+        for airport in self.stands.iter().map(|stand| stand.airport.as_str()).chain(self.areas.iter().map(|area| area.airport.as_str())) {
+            if !ret_val.iter().any(|existing| existing.eq_ignore_ascii_case(airport)) {
+                ret_val.push(airport);
+            }
+        }
+        ret_val
+    }
+
+    fn parse_stand_line(sections: &[&str]) -> SectorResult<GroundStand> {
+        if sections.len() < 5 || sections[1].is_empty() || sections[2].is_empty() {
+            return Err(Error::InvalidStand);
+        }
+        Ok(GroundStand {
+            airport: sections[1].to_string(),
+            name: sections[2].to_string(),
+            position: parse_position(&sections[3..])?,
+            radius: sections.get(5).and_then(|radius| radius.parse::<f64>().ok()),
+            wake_categories: None,
+            usage: None,
+            codes: None,
+            area: Vec::new(),
+        })
+    }
+
+    fn parse_stand_attribute(&mut self, keyword: &str, sections: &[&str]) -> SectorResult<()> {
+        let stand = self.stands.last_mut().ok_or(Error::InvalidStand)?;
+        let value = || sections.get(1).filter(|value| !value.is_empty()).map(|value| value.to_string());
+        match keyword {
+            "WTC" => stand.wake_categories = value(),
+            "USE" => stand.usage = value(),
+            "CODE" => stand.codes = value(),
+            _ => stand.area.push(parse_position(&sections[1..])?),
+        }
+        Ok(())
+    }
+
+    /// The non-empty lines that are not `//` or `;` comments, with their line numbers.
+    fn data_lines(reader: impl BufRead) -> anyhow::Result<Vec<(usize, String)>> {
+        let mut lines = Vec::new();
+        for (line_number, line) in reader.split(b'\n').enumerate() {
+            let line = String::from_utf8_lossy(&line?).trim().to_string();
+            if !line.is_empty() && !line.starts_with("//") && !line.starts_with(';') {
+                lines.push((line_number + 1, line));
+            }
+        }
+        Ok(lines)
+    }
+}
+
+fn parse_position(sections: &[&str]) -> SectorResult<Position<Valid>> {
+    match sections {
+        [lat, lon, ..] => Position::try_new_from_es(lat, lon)?.validate(),
+        _ => Err(Error::InvalidPosition),
+    }
+}
@@ -0,0 +1,116 @@
+use std::io::BufRead;
+
+/// A line of `ICAO_Aircraft.txt`: `designator<TAB>WTC and description<TAB>manufacturer<TAB>model`,
+/// e.g. `B738<TAB>ML2J<TAB>BOEING<TAB>737-800`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IcaoAircraft {
+    pub designator: String,
+    pub wake_category: Option<char>,
+    /// The ICAO aircraft description, e.g. `L2J` for a landplane with two jet engines.
+    pub description: String,
+    pub engine_count: Option<u8>,
+    pub engine_type: Option<char>,
+    pub manufacturer: String,
+    pub model: String,
+}
+impl IcaoAircraft {
+    fn from_items(items: &[&str]) -> Option<Self> {
+        let designator = items.first()?.trim();
+        if designator.is_empty() {
+            return None;
+        }
+        let info = items.get(1).map(|info| info.trim()).unwrap_or_default();
+        let (wake_category, description) = match info.chars().next() {
+            Some(wtc @ ('L' | 'M' | 'H' | 'J')) if info.len() == 4 => (Some(wtc), &info[1..]),
+            _ => (None, info),
+        };
+        let mut description_chars = description.chars().skip(1);
+        Some(IcaoAircraft {
+            designator: designator.to_string(),
+            wake_category,
+            description: description.to_string(),
+            engine_count: description_chars.next().and_then(|c| c.to_digit(10)).map(|count| count as u8),
+            engine_type: description_chars.next(),
+            manufacturer: items.get(2).map(|item| item.trim().to_string()).unwrap_or_default(),
+            model: items.get(3).map(|item| item.trim().to_string()).unwrap_or_default(),
+        })
+    }
+}
+
+/// A line of `ICAO_Airlines.txt`: `designator<TAB>name<TAB>telephony<TAB>country`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IcaoAirline {
+    pub designator: String,
+    pub name: String,
+    pub telephony: String,
+    pub country: String,
+}
+
+/// A line of `ICAO_Airports.txt`: `identifier<TAB>name<TAB>country`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IcaoAirport {
+    pub identifier: String,
+    pub name: String,
+    pub country: String,
+}
+
+/// The reference data files of a EuroScope package.
+#[derive(Debug, Clone, Default)]
+pub struct IcaoData {
+    pub aircraft: Vec<IcaoAircraft>,
+    pub airlines: Vec<IcaoAirline>,
+    pub airports: Vec<IcaoAirport>,
+}
+
+impl IcaoData {
+    pub fn try_load_aircraft(&mut self, reader: impl BufRead) -> anyhow::Result<()> {
+        for line in Self::data_lines(reader)? {
+            let items = line.split('\t').collect::<Vec<_>>();
+            if let Some(aircraft) = IcaoAircraft::from_items(&items) {
+                self.aircraft.push(aircraft);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn try_load_airlines(&mut self, reader: impl BufRead) -> anyhow::Result<()> {
+        for line in Self::data_lines(reader)? {
+            let items = line.split('\t').map(str::trim).collect::<Vec<_>>();
+            if items.len() >= 2 && !items[0].is_empty() {
+                self.airlines.push(IcaoAirline {
+                    designator: items[0].to_string(),
+                    name: items[1].to_string(),
+                    telephony: items.get(2).unwrap_or(&"").to_string(),
+                    country: items.get(3).unwrap_or(&"").to_string(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    pub fn try_load_airports(&mut self, reader: impl BufRead) -> anyhow::Result<()> {
+        for line in Self::data_lines(reader)? {
+            let items = line.split('\t').map(str::trim).collect::<Vec<_>>();
+            if items.len() >= 2 && !items[0].is_empty() {
+                self.airports.push(IcaoAirport {
+                    identifier: items[0].to_string(),
+                    name: items[1].to_string(),
+                    country: items.get(2).unwrap_or(&"").to_string(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// The non-empty lines that are not `;` comments. The files are often not UTF-8, so they are decoded lossily.
+    fn data_lines(reader: impl BufRead) -> anyhow::Result<Vec<String>> {
+        let mut lines = Vec::new();
+        for line in reader.split(b'\n') {
+            let line = String::from_utf8_lossy(&line?).trim_end_matches('\r').to_string();
+            if !line.trim().is_empty() && !line.starts_with(';') {
+                lines.push(line);
+            }
+        }
+        Ok(lines)
+    }
+}
@@ -1,11 +1,10 @@
-use std::{collections::HashMap, fs::File, io::{BufRead, BufReader}, path::{Path, PathBuf}, str::FromStr};
-use std::fs::read_dir;
+use std::{collections::HashMap, fs::File, io::{BufRead, BufReader, Read, Seek}, path::{Path, PathBuf}, str::FromStr, sync::Arc};
 use anyhow::Context;
 use directories::UserDirs;
 
 use crate::loaders::ese::{self, reader::EseReader, Ese};
 
-use super::{colour::Colour, reader::SctReader, sector::Sector, symbology::{SymbologyAttribute, SymbologyInfo, SymbologyItem}, EsAsr};
+use super::{alias::AliasFile, colour::Colour, error::Error, ground::GroundMaps, icao::IcaoData, navdata::WaypointIndex, topsky::TopSkyMaps, filesystem::{FileSystem, StdFileSystem, ZipFileSystem}, prf::{PrfSettings, PrfSettingsFile}, reader::SctReader, screen::{EsListSettings, EsScreenSettings}, sector::Sector, symbology::{SymbologyAttribute, SymbologyInfo, SymbologyItem}, tags::EsTags, EsAsr};
 
 #[derive(Debug, Default)]
 pub struct EuroScopeResultProfile {
@@ -13,18 +12,213 @@ pub struct EuroScopeResultProfile {
     pub prf_file: String,
     pub default_sector_id: String,
     pub symbology: SymbologyInfo,
-    pub asrs: HashMap<String, EsAsr>
+    pub asrs: HashMap<String, EsAsr>,
+    pub settings: PrfSettings,
+    pub alias: Option<AliasFile>,
+    pub icao_data: Option<IcaoData>,
+    pub topsky_maps: Option<TopSkyMaps>,
+    pub ground_maps: Option<GroundMaps>,
+    pub tags: Option<EsTags>,
+    pub screen_settings: Option<EsScreenSettings>,
+    pub list_settings: Option<EsListSettings>,
+    pub report: EuroScopeProfileReport
+}
+
+/// What was and was not loaded for one profile.
+///
+/// A profile is loaded from whichever of its files could be read, so a broken file leaves a gap in the
+/// profile instead of failing the whole package.
+#[derive(Debug, Clone, Default)]
+pub struct EuroScopeProfileReport {
+    pub prf_file: String,
+    /// Why the PRF itself could not be loaded. Nothing else was loaded for the profile if this is set.
+    pub load_error: Option<String>,
+    /// Files referenced by the profile that do not exist.
+    pub missing_files: Vec<String>,
+    /// Files that exist but could not be read, and why. The profile was loaded without them.
+    pub failed_files: Vec<(String, String)>,
+    /// Lines skipped in the files that were read, as the file, line number, line and error.
+    pub line_errors: Vec<(String, usize, String, Error)>,
+    /// The files the profile was loaded from, in the order they were read.
+    pub loaded_files: Vec<String>
+}
+
+impl EuroScopeProfileReport {
+    fn new(prf_file: &str) -> Self {
+        EuroScopeProfileReport {
+            prf_file: prf_file.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Whether every file of the profile was found and read without errors.
+    pub fn is_complete(&self) -> bool {
+        self.load_error.is_none() && self.missing_files.is_empty() && self.failed_files.is_empty() && self.line_errors.is_empty()
+    }
+
+    /// Runs `load` if `file` exists, noting the file as loaded, failed or missing.
+    fn load<T>(&mut self, fs: &dyn FileSystem, file: &str, load: impl FnOnce() -> anyhow::Result<T>) -> Option<T> {
+        if !fs.exists(Path::new(file)) {
+            self.add_missing_file(file);
+            return None;
+        }
+        match load() {
+            Ok(value) => {
+                self.loaded_files.push(file.to_string());
+                Some(value)
+            }
+            Err(e) => {
+                self.failed_files.push((file.to_string(), format!("{:#}", e)));
+                None
+            }
+        }
+    }
+
+    fn add_missing_file(&mut self, file: &str) {
+        if !self.missing_files.iter().any(|missing| missing == file) {
+            self.missing_files.push(file.to_string());
+        }
+    }
+
+    fn add_line_errors(&mut self, file: &str, errors: &[(usize, String, Error)]) {
+        self.line_errors
+            .extend(errors.iter().map(|(line_number, line, e)| (file.to_string(), *line_number, line.to_string(), *e)));
+    }
 }
 
 #[derive(Debug, Default)]
 pub struct EuroScopeResult {
     pub profiles: Vec<EuroScopeResultProfile>,
+    /// The reports of the PRFs that could not be loaded at all.
+    pub failed_profiles: Vec<EuroScopeProfileReport>,
     pub sectors: HashMap<String, (Sector, Option<Ese>)>,
 }
 
+impl EuroScopeResult {
+    /// The report of every PRF found, whether or not it was loaded.
+    pub fn reports(&self) -> impl Iterator<Item = &EuroScopeProfileReport> {
+        self.profiles.iter().map(|profile| &profile.report).chain(self.failed_profiles.iter())
+    }
+}
+
 #[derive(Debug)]
 pub struct EuroScopeLoader {
-    pub prfs: Vec<EuroScopeLoaderPrf>
+    pub prfs: Vec<EuroScopeLoaderPrf>,
+    /// Where all profile, sector, ESE, symbology and ASR files are read from.
+    pub fs: Arc<dyn FileSystem>,
+    pub path_resolver: EsPathResolver,
+    /// Used by the sector parser for names the sector files do not define.
+    pub waypoint_index: Option<Arc<WaypointIndex>>,
+    /// The PRFs found that could not be loaded, and why.
+    pub failed_prfs: Vec<(String, String)>
+}
+
+/// Resolves the paths written in PRF and ASR files to files in a [`FileSystem`].
+///
+/// Paths starting with `\` are relative to the PRF's folder. All other paths are looked up in each
+/// of the `roots` in order; if no root contains the file, the path under the last root is returned.
+#[derive(Debug, Clone)]
+pub struct EsPathResolver {
+    pub roots: Vec<PathBuf>,
+    /// Match each path component ignoring ASCII case, for Windows-authored paths on case-sensitive filesystems.
+    pub case_insensitive: bool
+}
+
+impl Default for EsPathResolver {
+    /// `EuroScope` in the user's config directory, then in their documents directory.
+    fn default() -> Self {
+        let mut roots = Vec::new();
+        if let Some(base_dirs) = directories::BaseDirs::new() {
+            roots.push(base_dirs.config_dir().join("EuroScope"));
+        }
+        if let Some(documents) = UserDirs::new().as_ref().and_then(UserDirs::document_dir) {
+            roots.push(documents.join("EuroScope"));
+        }
+        EsPathResolver::new(roots)
+    }
+}
+
+impl EsPathResolver {
+    pub fn new(roots: Vec<PathBuf>) -> Self {
+        EsPathResolver {
+            roots,
+            case_insensitive: false
+        }
+    }
+
+    /// Roots for a package read from an archive: the archive root and each of its top-level folders,
+    /// matched case-insensitively.
+    pub fn for_archive(fs: &dyn FileSystem) -> Self {
+        let root = PathBuf::from("/");
+        let mut roots = vec![root.clone()];
+        roots.extend(fs.read_dir(&root).unwrap_or_default().into_iter().filter(|path| fs.is_dir(path)));
+        EsPathResolver::new(roots).with_case_insensitive(true)
+    }
+
+    pub fn with_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.roots.push(root.into());
+        self
+    }
+
+    pub fn with_case_insensitive(mut self, case_insensitive: bool) -> Self {
+        self.case_insensitive = case_insensitive;
+        self
+    }
+
+    pub fn resolve(&self, fs: &dyn FileSystem, prf_file_path: impl AsRef<Path>, es_path: &str) -> anyhow::Result<PathBuf> {
+        let split_es_path = es_path.split("\\").collect::<Vec<&str>>();
+        let mut new_es_path = PathBuf::from("");
+        for item in &split_es_path {
+            new_es_path.push(item);
+        }
+
+        // Relative to PRF folder
+        if split_es_path.len() > 0 && split_es_path[0] == "" {
+            let prf_dir = prf_file_path
+                .as_ref()
+                .parent()
+                .context("Could not get parent dir of prf file!")?;
+            return Ok(self.find(fs, prf_dir, &new_es_path).unwrap_or_else(|| prf_dir.join(&new_es_path)));
+        }
+
+        if let Some(path) = self.roots.iter().find_map(|root| self.find(fs, root, &new_es_path)) {
+            return Ok(path);
+        }
+
+        Ok(self
+            .roots
+            .last()
+            .context("No EuroScope search roots configured!")?
+            .join(new_es_path))
+    }
+
+    /// `relative` under `root`, if it exists in `fs`.
+    fn find(&self, fs: &dyn FileSystem, root: &Path, relative: &Path) -> Option<PathBuf> {
+        let path = root.join(relative);
+        if fs.exists(&path) {
+            return Some(path);
+        }
+        if !self.case_insensitive {
+            return None;
+        }
+
+        let mut found = root.to_path_buf();
+        for component in relative.components() {
+            let name = component.as_os_str().to_str()?;
+            let exact = found.join(name);
+            found = if fs.exists(&exact) {
+                exact
+            } else {
+                fs.read_dir(&found).ok()?.into_iter().find(|entry| {
+                    entry
+                        .file_name()
+                        .and_then(|entry_name| entry_name.to_str())
+                        .is_some_and(|entry_name| entry_name.eq_ignore_ascii_case(name))
+                })?
+            };
+        }
+        Some(found)
+    }
 }
 
 #[derive(Debug)]
@@ -32,210 +226,426 @@ pub struct EuroScopeLoaderPrf {
     pub prf_file: String,
     pub symbology_file: String,
     pub sector_file: String,
-    pub asr_files: Vec<(String, String)>
+    pub asr_files: Vec<(String, String)>,
+    /// The settings files of the PRF that were found, resolved like the symbology and sector files.
+    pub settings_files: HashMap<PrfSettingsFile, String>,
+    /// The plugin DLLs of the PRF that were found.
+    pub plugin_files: Vec<String>,
+    /// The paths written in the PRF that could not be found. The symbology and sector files are empty if
+    /// theirs are missing.
+    pub missing_files: Vec<String>,
+    pub settings: PrfSettings
 }
 
 impl EuroScopeLoaderPrf {
     pub fn try_new_from_prf(prf_file: impl AsRef<Path>) -> anyhow::Result<EuroScopeLoaderPrf> {
-        // Vars
-        let mut symbology_file = "".to_string();
-        let mut sector_file = "".to_string();
-        let mut asrs: Vec<(String, String)> = Vec::new();
-
-        // Read PRF File
-        let reader = BufReader::new(File::open(&prf_file)?);
-
-        for line in reader.lines() {
-            if let Ok(ln) = line {
-                let items = ln.split("\t").collect::<Vec<&str>>();
-                if items.len() > 0 {
-                    match items[0].to_lowercase().as_str() {
-                        "settings" => {
-                            if items.len() >= 3 {
-                                match items[1].to_lowercase().as_str() {
-                                    "settingsfilesymbology" => {
-                                        symbology_file =
-                                            EuroScopeLoader::try_convert_es_path(&prf_file, items[2])?
-                                                .canonicalize()?
-                                                .to_str()
-                                                .unwrap()
-                                                .to_owned();
-                                    }
-                                    "sector" => {
-                                        sector_file =
-                                            EuroScopeLoader::try_convert_es_path(&prf_file, items[2])?
-                                                .canonicalize().context(format!("Error opening sector file {:?}!", items[2]))?
-                                                .to_str()
-                                                .unwrap()
-                                                .to_owned();
-                                    }
-                                    &_ => {}
-                                }
-                            }
-                        }
-                        "asrfastkeys" => {
-                            let asr_path = EuroScopeLoader::try_convert_es_path(&prf_file, items[2]);
-                            if let Ok(asr_path_res) = asr_path {
-                                let canon_path = asr_path_res.canonicalize();
-                                if let Ok(canon_res) = canon_path {
-                                    asrs.push((items[1].to_owned(), canon_res.to_str().unwrap().to_owned()));
-                                }
-                            }
-                        },
-                        &_ => {}
-                    }
-                }
+        Self::try_new_from_prf_with_fs(&StdFileSystem, prf_file)
+    }
+
+    pub fn try_new_from_prf_with_fs(fs: &dyn FileSystem, prf_file: impl AsRef<Path>) -> anyhow::Result<EuroScopeLoaderPrf> {
+        Self::try_new_from_prf_with_resolver(fs, &EsPathResolver::default(), prf_file)
+    }
+
+    pub fn try_new_from_prf_with_resolver(
+        fs: &dyn FileSystem,
+        path_resolver: &EsPathResolver,
+        prf_file: impl AsRef<Path>
+    ) -> anyhow::Result<EuroScopeLoaderPrf> {
+        let settings = PrfSettings::try_from_reader(fs.open(prf_file.as_ref())?)?;
+
+        let resolve = |es_path: &str| -> anyhow::Result<String> {
+            Ok(EuroScopeLoader::try_resolve_es_path(fs, path_resolver, &prf_file, es_path)?
+                .to_str()
+                .unwrap()
+                .to_owned())
+        };
+
+        // Files that cannot be found are left out and noted
+        let mut missing_files = Vec::new();
+        let mut find = |es_path: &str| {
+            let path = resolve(es_path).ok();
+            if path.is_none() && !missing_files.iter().any(|missing| missing == es_path) {
+                missing_files.push(es_path.to_string());
             }
-        }
+            path
+        };
+
+        let symbology_file = settings.file(PrfSettingsFile::Symbology).and_then(&mut find).unwrap_or_default();
+        let sector_file = settings.file(PrfSettingsFile::Sector).and_then(&mut find).unwrap_or_default();
+        let settings_files = PrfSettingsFile::ALL
+            .into_iter()
+            .filter_map(|file| Some((file, find(settings.file(file)?)?)))
+            .collect();
+        let asrs = settings
+            .asr_fast_keys
+            .iter()
+            .filter_map(|(key, es_path)| Some((key.to_owned(), find(es_path)?)))
+            .collect();
+        let plugin_files = settings.plugins.iter().filter_map(|es_path| find(es_path)).collect();
 
         Ok(EuroScopeLoaderPrf {
-            prf_file: prf_file
-                .as_ref()
-                .canonicalize()?
+            prf_file: fs
+                .canonicalize(prf_file.as_ref())?
                 .to_str()
                 .unwrap()
                 .to_string(),
-            symbology_file: symbology_file.to_string(),
-            sector_file: sector_file.to_string(),
-            asr_files: asrs
+            symbology_file,
+            sector_file,
+            asr_files: asrs,
+            settings_files,
+            plugin_files,
+            missing_files,
+            settings
         })
     }
 }
 
 impl EuroScopeLoader {
+    pub fn new(prfs: Vec<EuroScopeLoaderPrf>) -> EuroScopeLoader {
+        Self::with_fs(Arc::new(StdFileSystem), prfs)
+    }
+
+    pub fn with_fs(fs: Arc<dyn FileSystem>, prfs: Vec<EuroScopeLoaderPrf>) -> EuroScopeLoader {
+        Self::with_resolver(fs, EsPathResolver::default(), prfs)
+    }
+
+    pub fn with_resolver(fs: Arc<dyn FileSystem>, path_resolver: EsPathResolver, prfs: Vec<EuroScopeLoaderPrf>) -> EuroScopeLoader {
+        EuroScopeLoader { prfs, fs, path_resolver, waypoint_index: None, failed_prfs: Vec::new() }
+    }
+
+    pub fn with_waypoint_index(mut self, waypoint_index: Arc<WaypointIndex>) -> Self {
+        self.waypoint_index = Some(waypoint_index);
+        self
+    }
+
     pub fn try_new_from_dir(package_dir: impl AsRef<Path>) -> anyhow::Result<EuroScopeLoader> {
+        Self::try_new_from_dir_with_fs(Arc::new(StdFileSystem), package_dir)
+    }
+
+    /// Finds every PRF below `package_dir` in `fs`. PRFs that fail to load are kept in `failed_prfs`.
+    pub fn try_new_from_dir_with_fs(fs: Arc<dyn FileSystem>, package_dir: impl AsRef<Path>) -> anyhow::Result<EuroScopeLoader> {
+        Self::try_new_from_dir_with_resolver(fs, EsPathResolver::default(), package_dir)
+    }
+
+    pub fn try_new_from_dir_with_resolver(
+        fs: Arc<dyn FileSystem>,
+        path_resolver: EsPathResolver,
+        package_dir: impl AsRef<Path>
+    ) -> anyhow::Result<EuroScopeLoader> {
         let mut results = Vec::new();
-        if package_dir.as_ref().is_dir() {
-            for entry in read_dir(package_dir)? {
-                let entry = entry?;
-                let path = entry.path();
-                if path.is_dir() {
-                    results.append(&mut Self::try_new_from_dir(&path)?.prfs);
-                } else if entry.file_name().to_str().unwrap().contains(".prf"){
-                    if let Ok(result) = EuroScopeLoaderPrf::try_new_from_prf(&path) {
-                        results.push(result);
+        let mut failed_prfs = Vec::new();
+        Self::find_prfs(fs.as_ref(), &path_resolver, package_dir.as_ref(), &mut results, &mut failed_prfs)?;
+        let mut ret_val = Self::with_resolver(fs, path_resolver, results);
+        ret_val.failed_prfs = failed_prfs;
+        Ok(ret_val)
+    }
+
+    /// Finds every PRF in a zip archive, resolving the files they reference inside the archive.
+    pub fn try_new_from_zip(zip_file: impl AsRef<Path>) -> anyhow::Result<EuroScopeLoader> {
+        let fs = ZipFileSystem::try_from_file(&zip_file).context(format!("Error opening zip archive {:?}!", zip_file.as_ref()))?;
+        Self::try_new_from_archive(Arc::new(fs))
+    }
+
+    pub fn try_new_from_zip_reader(reader: impl Read + Seek) -> anyhow::Result<EuroScopeLoader> {
+        Self::try_new_from_archive(Arc::new(ZipFileSystem::try_from_zip(reader)?))
+    }
+
+    /// Finds every PRF in an archive mounted at `/`, using [`EsPathResolver::for_archive`].
+    pub fn try_new_from_archive(fs: Arc<dyn FileSystem>) -> anyhow::Result<EuroScopeLoader> {
+        let path_resolver = EsPathResolver::for_archive(fs.as_ref());
+        Self::try_new_from_dir_with_resolver(fs, path_resolver, "/")
+    }
+
+    fn find_prfs(
+        fs: &dyn FileSystem,
+        path_resolver: &EsPathResolver,
+        dir: &Path,
+        results: &mut Vec<EuroScopeLoaderPrf>,
+        failed_prfs: &mut Vec<(String, String)>
+    ) -> anyhow::Result<()> {
+        if fs.is_dir(dir) {
+            for path in fs.read_dir(dir)? {
+                if fs.is_dir(&path) {
+                    Self::find_prfs(fs, path_resolver, &path, results, failed_prfs)?;
+                } else if path.file_name().unwrap_or_default().to_str().unwrap_or_default().contains(".prf") {
+                    match EuroScopeLoaderPrf::try_new_from_prf_with_resolver(fs, path_resolver, &path) {
+                        Ok(result) => results.push(result),
+                        Err(e) => failed_prfs.push((path.to_str().unwrap_or_default().to_string(), format!("{:#}", e)))
                     }
                 }
             }
         }
-
-        Ok(EuroScopeLoader {
-            prfs: results
-        })
+        Ok(())
     }
 
+    /// Loads every profile from whichever of its files can be read. Each profile's report lists what was
+    /// left out.
     pub fn try_read(&mut self) -> anyhow::Result<EuroScopeResult> {
         let mut ret_val = EuroScopeResult::default();
 
+        for (prf_file, e) in &self.failed_prfs {
+            let mut report = EuroScopeProfileReport::new(prf_file);
+            report.load_error = Some(e.to_string());
+            ret_val.failed_profiles.push(report);
+        }
+
         for prf in &self.prfs {
-            let mut res_prf = EuroScopeResultProfile::default();
-
-            res_prf.prf_file = prf.prf_file.to_string();
-            res_prf.prf_name = Path::new(&prf.prf_file).file_stem().unwrap_or_default().to_str().unwrap().to_string();
-
-            // Load symbology
-            res_prf.symbology = SymbologyInfo::try_from_file(&prf.symbology_file)?;
-
-            res_prf.default_sector_id = prf.sector_file.to_string();
-
-            // Load Main Sector File
-            if (!ret_val.sectors.contains_key(&prf.sector_file)){
-                let sct_reader = SctReader::new(BufReader::new(File::open(&prf.sector_file)?));
-                let sct_result = sct_reader.try_read()?;
-                let ese_file = prf.sector_file.replace(".sct", ".ese");
-                let sct_ese_result = match std::fs::exists(&ese_file) {
-                    Ok(true) => {
-                        if let Ok(file) = File::open(&ese_file) {
-                            let reader = EseReader::new(BufReader::new(file));
-                            reader.try_read().ok()
-                        } else{
-                            None
-                        }
-                    },
-                    _ => None
-                };
+            let res_prf = self.read_profile(prf, &mut ret_val.sectors);
+            ret_val.profiles.push(res_prf);
+        }
+
+        Ok(ret_val)
+    }
+
+    fn read_profile(&self, prf: &EuroScopeLoaderPrf, sectors: &mut HashMap<String, (Sector, Option<Ese>)>) -> EuroScopeResultProfile {
+        let fs = self.fs.as_ref();
+        let mut report = EuroScopeProfileReport::new(&prf.prf_file);
+        let mut read_sectors = HashMap::new();
+        for missing in &prf.missing_files {
+            report.add_missing_file(missing);
+        }
+
+        let mut res_prf = EuroScopeResultProfile {
+            prf_file: prf.prf_file.to_string(),
+            prf_name: Path::new(&prf.prf_file).file_stem().unwrap_or_default().to_str().unwrap().to_string(),
+            default_sector_id: prf.sector_file.to_string(),
+            settings: prf.settings.clone(),
+            ..Default::default()
+        };
 
-                ret_val.sectors.insert(prf.sector_file.to_string(), (sct_result, sct_ese_result));
+        // Load symbology
+        if !prf.symbology_file.is_empty() {
+            let symbology = report.load(fs, &prf.symbology_file, || SymbologyInfo::try_from_reader(fs.open(Path::new(&prf.symbology_file))?, &prf.symbology_file));
+            if let Some(symbology) = symbology {
+                report.add_line_errors(&prf.symbology_file, &symbology.non_critical_errors);
+                res_prf.symbology = symbology;
             }
+        }
+
+        // Load alias file
+        if let Some(alias_file) = prf.settings_files.get(&PrfSettingsFile::Alias) {
+            res_prf.alias = report.load(fs, alias_file, || AliasFile::try_from_reader(fs.open(Path::new(alias_file))?, alias_file));
+        }
+
+        // Load tag definitions
+        if let Some(tags_file) = prf.settings_files.get(&PrfSettingsFile::Tags) {
+            res_prf.tags = report.load(fs, tags_file, || EsTags::try_from_reader(fs.open(Path::new(tags_file))?, tags_file));
+            if let Some(tags) = &res_prf.tags {
+                report.add_line_errors(tags_file, &tags.non_critical_errors);
+            }
+        }
+
+        // Load screen and list settings
+        if let Some(screen_file) = prf.settings_files.get(&PrfSettingsFile::Screen) {
+            res_prf.screen_settings = report.load(fs, screen_file, || EsScreenSettings::try_from_reader(fs.open(Path::new(screen_file))?, screen_file));
+        }
+        if let Some(lists_file) = prf.settings_files.get(&PrfSettingsFile::Lists) {
+            res_prf.list_settings = report.load(fs, lists_file, || EsListSettings::try_from_reader(fs.open(Path::new(lists_file))?, lists_file));
+            if let Some(list_settings) = &res_prf.list_settings {
+                report.add_line_errors(lists_file, &list_settings.non_critical_errors);
+            }
+        }
+
+        // Load ICAO reference data
+        for file in [PrfSettingsFile::Aircraft, PrfSettingsFile::Airlines, PrfSettingsFile::Airports] {
+            if let Some(path) = prf.settings_files.get(&file) {
+                let icao_data = res_prf.icao_data.get_or_insert_with(IcaoData::default);
+                report.load(fs, path, || {
+                    let reader = fs.open(Path::new(path))?;
+                    match file {
+                        PrfSettingsFile::Aircraft => icao_data.try_load_aircraft(reader),
+                        PrfSettingsFile::Airlines => icao_data.try_load_airlines(reader),
+                        _ => icao_data.try_load_airports(reader),
+                    }
+                });
+            }
+        }
+
+        // Load Main Sector File
+        if !prf.sector_file.is_empty() {
+            self.read_sector(sectors, &prf.sector_file, &mut report, &mut read_sectors);
+        }
+
+        // Load TopSky maps, which are kept next to the plugin
+        let sector = sectors.get(&prf.sector_file).map(|sector| &sector.0);
+        res_prf.topsky_maps = Self::read_topsky_maps(fs, &prf.plugin_files, sector, &mut report);
 
-            // Load ASRs
-            for asr_source in &prf.asr_files {
-                let mut asr = EsAsr::try_from_asr_file(&asr_source.1)?;
-                if !asr.1.is_empty() {
-                    if let Ok(asr_sector_pbuf) = Self::try_convert_es_path(&prf.prf_file, &asr.1)?.canonicalize() {
+        // Load ground radar plugin stands and maps
+        res_prf.ground_maps = Self::read_ground_maps(fs, &prf.plugin_files, &mut report);
+
+        // Load ASRs
+        for asr_source in &prf.asr_files {
+            let Some(mut asr) = report.load(fs, &asr_source.1, || EsAsr::try_from_reader(fs.open(Path::new(&asr_source.1))?, &asr_source.1)) else {
+                continue;
+            };
+            report.add_line_errors(&asr_source.1, &asr.0.non_critical_errors);
+            asr.0.sector_file_id = Some(res_prf.default_sector_id.clone());
+            if !asr.1.is_empty() {
+                match Self::try_resolve_es_path(fs, &self.path_resolver, &prf.prf_file, &asr.1) {
+                    Ok(asr_sector_pbuf) => {
                         let asr_sector_path = asr_sector_pbuf.as_os_str().to_str().unwrap_or_default().to_string();
-                        if !ret_val.sectors.contains_key(&asr_sector_path) {
-                            let asr_sct_reader = SctReader::new(BufReader::new(File::open(&asr_sector_path)?));
-                            let asr_sct_result = asr_sct_reader.try_read()?;
-    
-                            let asr_ese_file = asr_sector_path.replace(".sct", ".ese");
-                            let asr_sct_ese_result = match std::fs::exists(&asr_ese_file) {
-                                Ok(true) => {
-                                    if let Ok(file) = File::open(&asr_ese_file) {
-                                        let reader = EseReader::new(BufReader::new(file));
-                                        reader.try_read().ok()
-                                    } else {
-                                        None
-                                    }
-                                },
-                                _ => None
-                            };
-                            ret_val.sectors.insert(asr_sector_path.to_string(), (asr_sct_result, asr_sct_ese_result));
+                        if self.read_sector(sectors, &asr_sector_path, &mut report, &mut read_sectors) {
+                            asr.0.sector_file_id = Some(asr_sector_path);
                         }
-                        asr.0.sector_file_id = Some(asr_sector_path.to_string());
-                    } else {
-                        asr.0.sector_file_id = Some(res_prf.default_sector_id.clone());
-                    }                 
-                } else {
-                    asr.0.sector_file_id = Some(res_prf.default_sector_id.clone());
+                    }
+                    Err(_) => report.add_missing_file(&asr.1)
                 }
-                asr.0.name = Path::new(&asr_source.1).file_stem().unwrap_or_default().to_str().unwrap().to_string();
-
-                res_prf.asrs.insert(asr_source.0.to_string(), asr.0);
             }
+            asr.0.name = Path::new(&asr_source.1).file_stem().unwrap_or_default().to_str().unwrap().to_string();
 
-            ret_val.profiles.push(res_prf);
+            res_prf.asrs.insert(asr_source.0.to_string(), asr.0);
         }
 
-        Ok(ret_val)
+        res_prf.report = report;
+        res_prf
     }
 
-    pub fn try_convert_es_path(
-        prf_file_path: impl AsRef<Path>,
-        es_path: &str,
-    ) -> anyhow::Result<PathBuf> {
-        let split_es_path = es_path.split("\\").collect::<Vec<&str>>();
-        let mut new_es_path = PathBuf::from("");
-        for item in &split_es_path {
-            new_es_path.push(item);
+    /// Reads `TopSkyMaps.txt` and the colours of `TopSkySettings.txt` from the folder of the TopSky plugin.
+    fn read_topsky_maps(
+        fs: &dyn FileSystem,
+        plugin_files: &[String],
+        sector: Option<&Sector>,
+        report: &mut EuroScopeProfileReport
+    ) -> Option<TopSkyMaps> {
+        let plugin_dir = plugin_files.iter().map(Path::new).find_map(|plugin| {
+            let file_name = plugin.file_name()?.to_str()?.to_lowercase();
+            file_name.starts_with("topsky").then(|| plugin.parent()).flatten()
+        });
+        let maps_file = plugin_dir.map(|dir| dir.join("TopSkyMaps.txt")).filter(|file| fs.exists(file))?;
+        let maps_path = maps_file.to_str().unwrap_or_default();
+
+        let mut maps = report.load(fs, maps_path, || TopSkyMaps::try_from_reader(fs.open(&maps_file)?, maps_path, sector))?;
+        report.add_line_errors(maps_path, &maps.non_critical_errors);
+        let settings_file = maps_file.with_file_name("TopSkySettings.txt");
+        if fs.exists(&settings_file) {
+            report.load(fs, settings_file.to_str().unwrap_or_default(), || maps.colours.try_load_settings(fs.open(&settings_file)?));
         }
+        Some(maps)
+    }
 
-        // Relative to PRF folder
-        if split_es_path.len() > 0 && split_es_path[0] == "" {
-            return Ok(prf_file_path
-                .as_ref()
-                .parent()
-                .context("Could not get parent dir of prf file!")?
-                .join(new_es_path));
+    /// Reads the stand and map files from the folders of the GRplugin and vSMR plugins.
+    ///
+    /// Text files with `stand` in their name are read as stand files and those with `map` in their name as map files.
+    fn read_ground_maps(fs: &dyn FileSystem, plugin_files: &[String], report: &mut EuroScopeProfileReport) -> Option<GroundMaps> {
+        let plugin_dirs = plugin_files.iter().map(Path::new).filter_map(|plugin| {
+            let file_name = plugin.file_name()?.to_str()?.to_lowercase();
+            (file_name.starts_with("grplugin") || file_name.starts_with("vsmr")).then(|| plugin.parent()).flatten()
+        });
+
+        let mut ret_val: Option<GroundMaps> = None;
+        for plugin_dir in plugin_dirs {
+            let mut files = match fs.read_dir(plugin_dir) {
+                Ok(files) => files,
+                Err(e) => {
+                    report.failed_files.push((plugin_dir.to_str().unwrap_or_default().to_string(), e.to_string()));
+                    continue;
+                }
+            };
+            files.sort();
+            for file in files {
+                let Some(file_name) = file.file_name().and_then(|name| name.to_str()).map(str::to_lowercase) else {
+                    continue;
+                };
+                if !file_name.ends_with(".txt") || fs.is_dir(&file) {
+                    continue;
+                }
+                let path = file.to_str().unwrap_or_default();
+                let ground_maps = ret_val.get_or_insert_with(GroundMaps::default);
+                let error_count = ground_maps.non_critical_errors.len();
+                if file_name.contains("stand") {
+                    report.load(fs, path, || ground_maps.try_load_stands(fs.open(&file)?));
+                } else if file_name.contains("map") {
+                    report.load(fs, path, || ground_maps.try_load_maps(fs.open(&file)?));
+                }
+                report.add_line_errors(path, &ground_maps.non_critical_errors[error_count..]);
+            }
+        }
+        ret_val
+    }
+
+    /// Loads `sector_file` into `sectors` if it is not there yet. Returns whether it is there.
+    ///
+    /// `read_sectors` holds the sector files already added to `report` and whether they loaded, so that a
+    /// sector used by several ASRs of the profile is only reported once.
+    fn read_sector(
+        &self,
+        sectors: &mut HashMap<String, (Sector, Option<Ese>)>,
+        sector_file: &str,
+        report: &mut EuroScopeProfileReport,
+        read_sectors: &mut HashMap<String, bool>
+    ) -> bool {
+        if let Some(loaded) = read_sectors.get(sector_file) {
+            return *loaded;
         }
+        let loaded = self.read_sector_into_report(sectors, sector_file, report);
+        read_sectors.insert(sector_file.to_string(), loaded);
+        loaded
+    }
 
-        let path = 
-        directories::BaseDirs::new()
-        .context("Failed to get User Directories")?
-        .config_dir()
-        .join("EuroScope")
-        .join(new_es_path.clone());
+    fn read_sector_into_report(
+        &self,
+        sectors: &mut HashMap<String, (Sector, Option<Ese>)>,
+        sector_file: &str,
+        report: &mut EuroScopeProfileReport
+    ) -> bool {
+        // The ESE is optional, so a missing one is not reported
+        let ese_file = sector_file.replace(".sct", ".ese");
+        if !sectors.contains_key(sector_file) {
+            let fs = self.fs.as_ref();
+            let Some(sector) = report.load(fs, sector_file, || self.try_read_sector(sector_file)) else {
+                return false;
+            };
+            let ese = fs
+                .exists(Path::new(&ese_file))
+                .then(|| report.load(fs, &ese_file, || Ok(EseReader::new(fs.open(Path::new(&ese_file))?).try_read()?)))
+                .flatten();
+            sectors.insert(sector_file.to_string(), (sector, ese));
+        } else {
+            report.loaded_files.push(sector_file.to_string());
+            if sectors[sector_file].1.is_some() {
+                report.loaded_files.push(ese_file.clone());
+            }
+        }
 
+        let (sector, ese) = &sectors[sector_file];
+        report.add_line_errors(sector_file, &sector.non_critical_errors);
+        if let Some(ese) = ese {
+            report.add_line_errors(&ese_file, &ese.non_critical_errors);
+        }
+        true
+    }
 
-        if path.exists() {
-            return Ok(path);
+    fn try_read_sector(&self, sector_file: &str) -> anyhow::Result<Sector> {
+        let mut sct_reader = SctReader::new(self.fs.open(Path::new(sector_file))?);
+        if let Some(waypoint_index) = &self.waypoint_index {
+            sct_reader = sct_reader.with_waypoint_index(waypoint_index.clone());
         }
+        Ok(sct_reader.try_read()?)
+    }
 
-        Ok(UserDirs::new()
-            .context("Failed to get User Directories")?
-            .document_dir()
-            .context("Could not get Documents dir!")?
-            .join("EuroScope")
-            .join(new_es_path))
+    /// Converts an ES path with `path_resolver` and canonicalizes it in `fs`.
+    fn try_resolve_es_path(
+        fs: &dyn FileSystem,
+        path_resolver: &EsPathResolver,
+        prf_file_path: impl AsRef<Path>,
+        es_path: &str
+    ) -> anyhow::Result<PathBuf> {
+        let path = path_resolver.resolve(fs, prf_file_path, es_path)?;
+        Ok(fs.canonicalize(&path)?)
+    }
+
+    pub fn try_convert_es_path(
+        prf_file_path: impl AsRef<Path>,
+        es_path: &str,
+    ) -> anyhow::Result<PathBuf> {
+        Self::try_convert_es_path_with_fs(&StdFileSystem, prf_file_path, es_path)
+    }
+
+    pub fn try_convert_es_path_with_fs(
+        fs: &dyn FileSystem,
+        prf_file_path: impl AsRef<Path>,
+        es_path: &str,
+    ) -> anyhow::Result<PathBuf> {
+        EsPathResolver::default().resolve(fs, prf_file_path, es_path)
     }
 }
@@ -1,18 +1,27 @@
 use std::{fs::File, io::BufReader, str::FromStr};
 
 use error::Error;
+pub mod alias;
 pub mod colour;
 pub mod error;
+pub mod icao;
 pub mod line;
 pub(crate) mod partial;
 pub mod position;
 pub mod reader;
+pub mod screen;
 pub mod sector;
 pub mod waypoint;
 pub mod symbology;
 pub mod loader;
+pub mod navdata;
+pub mod prf;
+pub mod tags;
+pub mod filesystem;
+pub mod ground;
+pub mod topsky;
 mod asr;
-pub use asr::EsAsr;
+pub use asr::{DisplayItem, EsAsr, PluginItem};
 
 pub type SectorResult<T> = std::result::Result<T, error::Error>;
 
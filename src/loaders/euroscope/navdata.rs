@@ -0,0 +1,337 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+    io::BufRead,
+    path::Path,
+};
+
+use aviation_calc_util::geo::GeoPoint;
+
+use super::{
+    error::Error,
+    filesystem::FileSystem,
+    position::{Position, Valid},
+    SectorResult,
+};
+
+/// Named points that can be looked up by identifier. Identifiers are not unique worldwide, so every
+/// position is kept and the caller picks the nearest.
+#[derive(Debug, Clone, Default)]
+pub struct WaypointIndex {
+    waypoints: HashMap<String, Vec<Position<Valid>>>,
+}
+
+impl WaypointIndex {
+    pub fn insert(&mut self, identifier: &str, position: Position<Valid>) {
+        self.waypoints.entry(identifier.to_uppercase()).or_default().push(position);
+    }
+
+    pub fn len(&self) -> usize {
+        self.waypoints.values().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.waypoints.is_empty()
+    }
+
+    /// Every position with the identifier (case-insensitive).
+    pub fn find_all(&self, identifier: &str) -> &[Position<Valid>] {
+        self.waypoints.get(&identifier.to_uppercase()).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// The position with the identifier that is closest to `near`, or the first one if `near` is not given.
+    pub fn find_nearest(&self, identifier: &str, near: Option<Position<Valid>>) -> Option<Position<Valid>> {
+        let positions = self.find_all(identifier);
+        match near {
+            Some(near) => positions
+                .iter()
+                .min_by(|a, b| distance_nm(**a, near).total_cmp(&distance_nm(**b, near)))
+                .copied(),
+            None => positions.first().copied(),
+        }
+    }
+}
+
+/// Which airway layer a segment belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AirwayLevel {
+    Low,
+    High,
+    Both,
+}
+impl AirwayLevel {
+    fn from_es(value: &str) -> SectorResult<Self> {
+        match value {
+            "L" => Ok(Self::Low),
+            "H" => Ok(Self::High),
+            "B" => Ok(Self::Both),
+            _ => Err(Error::InvalidAirway),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AirwayFix {
+    pub identifier: String,
+    pub position: Position<Valid>,
+}
+
+/// A segment that may be flown from one fix to the next.
+#[derive(Debug, Clone)]
+pub struct AirwayEdge {
+    pub to: usize,
+    pub airway: String,
+    pub level: AirwayLevel,
+    pub min_fl: Option<u32>,
+    pub max_fl: Option<u32>,
+    pub distance_nm: f64,
+}
+impl AirwayEdge {
+    pub fn allows_level(&self, flight_level: u32) -> bool {
+        self.min_fl.is_none_or(|min| flight_level >= min) && self.max_fl.is_none_or(|max| flight_level <= max)
+    }
+}
+
+/// A path through the airway graph.
+#[derive(Debug, Clone)]
+pub struct AirwayRoute {
+    pub fixes: Vec<AirwayFix>,
+    /// The airway of each leg, so one shorter than `fixes`.
+    pub airways: Vec<String>,
+    pub distance_nm: f64,
+}
+
+/// The airway network of `airway.txt`, as a directed graph of fixes.
+///
+/// Fixes are identified by name and position, as the same name can be used in different places.
+#[derive(Debug, Clone, Default)]
+pub struct AirwayGraph {
+    fixes: Vec<AirwayFix>,
+    edges: Vec<Vec<AirwayEdge>>,
+    fix_index: HashMap<(String, i64, i64), usize>,
+}
+
+impl AirwayGraph {
+    pub fn fixes(&self) -> &[AirwayFix] {
+        &self.fixes
+    }
+
+    pub fn edges(&self, fix: usize) -> &[AirwayEdge] {
+        self.edges.get(fix).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Parses one line of `airway.txt`:
+    ///
+    /// `fix:lat:lon:region:airway:level:prev fix:lat:lon:min FL:max FL:valid:next fix:lat:lon:min FL:max FL:valid`
+    ///
+    /// with tabs as separators and `L`, `H` or `B` as level. A `Y` in the valid field means the segment
+    /// may be flown from this fix towards that neighbour. Empty neighbours mark the end of the airway,
+    /// and a maximum FL of 0 means no upper limit.
+    pub fn parse_airway_line(&mut self, line: &str) -> SectorResult<()> {
+        let items = line.split('\t').map(str::trim).collect::<Vec<_>>();
+        if items.len() < 6 || items[0].is_empty() || items[4].is_empty() {
+            return Err(Error::InvalidAirway);
+        }
+        let from = self.fix(items[0], Self::parse_position(items[1], items[2])?);
+        let airway = items[4].to_string();
+        let level = AirwayLevel::from_es(items[5])?;
+
+        for neighbour in [items.get(6..12), items.get(12..18)].into_iter().flatten() {
+            if neighbour[0].is_empty() || neighbour[5] != "Y" {
+                continue;
+            }
+            let to = self.fix(neighbour[0], Self::parse_position(neighbour[1], neighbour[2])?);
+            let min_fl = neighbour[3].parse::<u32>().map_err(|_| Error::InvalidAirway)?;
+            let max_fl = neighbour[4].parse::<u32>().map_err(|_| Error::InvalidAirway)?;
+            if self.edges[from].iter().any(|edge| edge.to == to && edge.airway == airway) {
+                continue;
+            }
+            let distance_nm = distance_nm(self.fixes[from].position, self.fixes[to].position);
+            self.edges[from].push(AirwayEdge {
+                to,
+                airway: airway.to_string(),
+                level,
+                min_fl: Some(min_fl).filter(|fl| *fl > 0),
+                max_fl: Some(max_fl).filter(|fl| *fl > 0),
+                distance_nm,
+            });
+        }
+        Ok(())
+    }
+
+    fn parse_position(lat: &str, lon: &str) -> SectorResult<Position<Valid>> {
+        parse_navdata_position(lat, lon).map_err(|_| Error::InvalidAirway)
+    }
+
+    fn fix(&mut self, identifier: &str, position: Position<Valid>) -> usize {
+        let key = (
+            identifier.to_uppercase(),
+            (position.lat * 1e6).round() as i64,
+            (position.lon * 1e6).round() as i64,
+        );
+        if let Some(index) = self.fix_index.get(&key) {
+            return *index;
+        }
+        self.fixes.push(AirwayFix {
+            identifier: identifier.to_string(),
+            position,
+        });
+        self.edges.push(Vec::new());
+        self.fix_index.insert(key, self.fixes.len() - 1);
+        self.fixes.len() - 1
+    }
+
+    /// All fixes with the identifier (case-insensitive).
+    pub fn find_fixes(&self, identifier: &str) -> Vec<usize> {
+        (0..self.fixes.len())
+            .filter(|index| self.fixes[*index].identifier.eq_ignore_ascii_case(identifier))
+            .collect()
+    }
+
+    pub fn has_airway(&self, airway: &str) -> bool {
+        self.edges.iter().flatten().any(|edge| edge.airway.eq_ignore_ascii_case(airway))
+    }
+
+    /// The fixes along `airway` from `from` to `to`, both included, following its permitted direction.
+    pub fn expand_airway(&self, airway: &str, from: &str, to: &str) -> Option<AirwayRoute> {
+        self.shortest_path(from, to, |edge| edge.airway.eq_ignore_ascii_case(airway))
+    }
+
+    /// The shortest route from `from` to `to` over the airway network.
+    ///
+    /// If `flight_level` is given, only segments whose level limits allow it are used.
+    pub fn find_route(&self, from: &str, to: &str, flight_level: Option<u32>) -> Option<AirwayRoute> {
+        self.shortest_path(from, to, |edge| flight_level.is_none_or(|fl| edge.allows_level(fl)))
+    }
+
+    fn shortest_path(&self, from: &str, to: &str, usable: impl Fn(&AirwayEdge) -> bool) -> Option<AirwayRoute> {
+        let targets = self.find_fixes(to);
+        // Distances are compared in thousandths of a nautical mile
+        let mut costs = vec![u64::MAX; self.fixes.len()];
+        let mut previous: Vec<Option<(usize, usize)>> = vec![None; self.fixes.len()];
+        let mut queue = BinaryHeap::new();
+        for start in self.find_fixes(from) {
+            costs[start] = 0;
+            queue.push(Reverse((0_u64, start)));
+        }
+
+        while let Some(Reverse((cost, fix))) = queue.pop() {
+            if cost > costs[fix] {
+                continue;
+            }
+            if targets.contains(&fix) {
+                return Some(self.build_route(fix, &previous));
+            }
+            for (edge_index, edge) in self.edges[fix].iter().enumerate() {
+                if !usable(edge) {
+                    continue;
+                }
+                let next_cost = cost + (edge.distance_nm * 1000.0).round() as u64;
+                if next_cost < costs[edge.to] {
+                    costs[edge.to] = next_cost;
+                    previous[edge.to] = Some((fix, edge_index));
+                    queue.push(Reverse((next_cost, edge.to)));
+                }
+            }
+        }
+        None
+    }
+
+    fn build_route(&self, end: usize, previous: &[Option<(usize, usize)>]) -> AirwayRoute {
+        let mut fixes = vec![self.fixes[end].clone()];
+        let mut airways = Vec::new();
+        let mut distance_nm = 0.0;
+        let mut current = end;
+        while let Some((from, edge_index)) = previous[current] {
+            let edge = &self.edges[from][edge_index];
+            fixes.push(self.fixes[from].clone());
+            airways.push(edge.airway.to_string());
+            distance_nm += edge.distance_nm;
+            current = from;
+        }
+        fixes.reverse();
+        airways.reverse();
+        AirwayRoute {
+            fixes,
+            airways,
+            distance_nm,
+        }
+    }
+}
+
+/// The contents of a EuroScope NavData folder.
+#[derive(Debug, Clone, Default)]
+pub struct NavData {
+    pub intersections: WaypointIndex,
+    pub airways: AirwayGraph,
+    pub non_critical_errors: Vec<(usize, String, Error)>,
+}
+
+impl NavData {
+    /// Reads `isec.txt` and `airway.txt` from `dir`. Either file may be missing.
+    pub fn try_from_dir_with_fs(fs: &dyn FileSystem, dir: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let mut navdata = Self::default();
+        let isec_file = dir.as_ref().join("isec.txt");
+        if fs.exists(&isec_file) {
+            navdata.try_load_isec(fs.open(&isec_file)?)?;
+        }
+        let airway_file = dir.as_ref().join("airway.txt");
+        if fs.exists(&airway_file) {
+            navdata.try_load_airways(fs.open(&airway_file)?)?;
+        }
+        Ok(navdata)
+    }
+
+    /// Reads `isec.txt`, with lines of `identifier<TAB>lat<TAB>lon<TAB>type`.
+    pub fn try_load_isec(&mut self, reader: impl BufRead) -> anyhow::Result<()> {
+        for (line_number, line) in Self::data_lines(reader)? {
+            let items = line.split('\t').map(str::trim).collect::<Vec<_>>();
+            let position = match items.as_slice() {
+                [identifier, lat, lon, ..] if !identifier.is_empty() => parse_navdata_position(lat, lon),
+                _ => Err(Error::InvalidIntersection),
+            };
+            match position {
+                Ok(position) => self.intersections.insert(items[0], position),
+                Err(_) => self.non_critical_errors.push((line_number, line.to_string(), Error::InvalidIntersection)),
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads `airway.txt`, see [`AirwayGraph::parse_airway_line`].
+    pub fn try_load_airways(&mut self, reader: impl BufRead) -> anyhow::Result<()> {
+        for (line_number, line) in Self::data_lines(reader)? {
+            if let Err(e) = self.airways.parse_airway_line(&line) {
+                self.non_critical_errors.push((line_number, line.to_string(), e));
+            }
+        }
+        Ok(())
+    }
+
+    /// The non-empty lines that are not `;` or `//` comments, with their line numbers.
+    fn data_lines(reader: impl BufRead) -> anyhow::Result<Vec<(usize, String)>> {
+        let mut lines = Vec::new();
+        for (line_number, line) in reader.split(b'\n').enumerate() {
+            let line = String::from_utf8_lossy(&line?).trim_end().to_string();
+            let trimmed = line.trim_start();
+            if !trimmed.is_empty() && !trimmed.starts_with(';') && !trimmed.starts_with("//") {
+                lines.push((line_number + 1, line));
+            }
+        }
+        Ok(lines)
+    }
+}
+
+/// NavData files use decimal degrees, but some exports use the sector file format (`N051.28.39.000`).
+fn parse_navdata_position(lat: &str, lon: &str) -> SectorResult<Position<Valid>> {
+    match (lat.parse::<f64>(), lon.parse::<f64>()) {
+        (Ok(lat), Ok(lon)) => Position::new(lat, lon).validate(),
+        _ => Position::try_new_from_es(lat, lon)?.validate(),
+    }
+}
+
+fn distance_nm(a: Position<Valid>, b: Position<Valid>) -> f64 {
+    GeoPoint::from_degs_and_ft(a.lat, a.lon, 0.0)
+        .flat_distance(&GeoPoint::from_degs_and_ft(b.lat, b.lon, 0.0))
+        .as_nautical_miles()
+}
@@ -1,9 +1,10 @@
-use std::{collections::HashMap, str::FromStr};
+use std::{collections::HashMap, str::FromStr, sync::Arc};
 
 use super::{
     colour::Colour,
     error::Error,
     line::{ColouredLine, LineGroup},
+    navdata::WaypointIndex,
     position::{self, Heading, Position},
     sector::{Label, LabelGroup},
     waypoint::{self, Airport, Fix, Ndb, RunwayEnd, RunwayModifier, RunwayStrip, Vor},
@@ -52,6 +53,8 @@ pub struct PartialSector {
     pub geo_entries: Vec<LineGroup<ColouredLine>>,
     pub region_groups: Vec<PartialRegionGroup>,
     pub labels: Vec<LabelGroup>,
+    /// Looked up when a name is not defined in the sector file itself, e.g. intersections from NavData.
+    pub waypoint_index: Option<Arc<WaypointIndex>>,
     position_creator: PositionCreator,
     current_region_name: String,
 }
@@ -96,6 +99,14 @@ impl PartialSector {
                 return Some((airport.position.into()));
             }
         }
+        if let Some(waypoint_index) = &self.waypoint_index {
+            let centre = self
+                .sector_info
+                .default_centre_pt_lat
+                .zip(self.sector_info.default_centre_pt_lon)
+                .and_then(|(lat, lon)| Position::new(lat, lon).validate().ok());
+            return waypoint_index.find_nearest(lat, centre).map(Position::from);
+        }
 
         return None;
     }
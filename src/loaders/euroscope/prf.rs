@@ -0,0 +1,158 @@
+use std::{collections::HashMap, io::BufRead};
+
+/// The settings files a PRF can point to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PrfSettingsFile {
+    Symbology,
+    Tags,
+    Screen,
+    Lists,
+    Plugins,
+    General,
+    Voice,
+    Sector,
+    Alias,
+    Airlines,
+    Airports,
+    Aircraft,
+}
+impl PrfSettingsFile {
+    pub const ALL: [PrfSettingsFile; 12] = [
+        Self::Symbology,
+        Self::Tags,
+        Self::Screen,
+        Self::Lists,
+        Self::Plugins,
+        Self::General,
+        Self::Voice,
+        Self::Sector,
+        Self::Alias,
+        Self::Airlines,
+        Self::Airports,
+        Self::Aircraft,
+    ];
+
+    fn from_key(key: &str) -> Option<Self> {
+        let file = match key.to_lowercase().as_str() {
+            "settingsfilesymbology" => Self::Symbology,
+            "settingsfiletags" => Self::Tags,
+            "settingsfilescreen" => Self::Screen,
+            "settingsfilelists" => Self::Lists,
+            "settingsfileplugins" => Self::Plugins,
+            "settingsfilegeneral" => Self::General,
+            "settingsfilevoice" => Self::Voice,
+            "sector" => Self::Sector,
+            "alias" => Self::Alias,
+            "airlines" => Self::Airlines,
+            "airports" => Self::Airports,
+            "aircraft" => Self::Aircraft,
+            _ => return None,
+        };
+        Some(file)
+    }
+}
+
+/// The connection details of the last session, from the `LastSession` lines.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PrfLastSession {
+    pub callsign: Option<String>,
+    pub real_name: Option<String>,
+    pub certificate: Option<String>,
+    pub rating: Option<u8>,
+    pub facility: Option<u8>,
+    pub server: Option<String>,
+}
+
+/// Every line of a PRF file. Paths are kept as written, in EuroScope's `\`-separated form.
+#[derive(Debug, Clone, Default)]
+pub struct PrfSettings {
+    pub files: HashMap<PrfSettingsFile, String>,
+    pub last_session: PrfLastSession,
+    pub recent_files: Vec<String>,
+    /// ASR fast keys and their ASR paths, in the order they appear in the PRF.
+    pub asr_fast_keys: Vec<(String, String)>,
+    pub plugins: Vec<String>,
+    /// Lines that are not understood, as `(section, key, value)`.
+    pub unknown: Vec<(String, String, String)>,
+}
+
+impl PrfSettings {
+    pub fn try_from_reader(reader: impl BufRead) -> anyhow::Result<Self> {
+        let mut ret_val = Self::default();
+
+        for line in reader.lines() {
+            // Lines that are not valid UTF-8 are skipped, as in the other EuroScope files
+            let Ok(line) = line else {
+                continue;
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            let items = line.splitn(3, '\t').map(str::trim_end).collect::<Vec<&str>>();
+            let [section, key, value] = items[..] else {
+                // Kept as unknown, with the missing fields empty
+                let field = |index: usize| items.get(index).map(|item| item.to_string()).unwrap_or_default();
+                ret_val.unknown.push((field(0), field(1), String::new()));
+                continue;
+            };
+
+            let known = match section.to_lowercase().as_str() {
+                "settings" => PrfSettingsFile::from_key(key).map(|file| ret_val.files.insert(file, value.to_string())).is_some(),
+                "lastsession" => ret_val.parse_last_session(key, value),
+                "recentfiles" => {
+                    ret_val.recent_files.push(value.to_string());
+                    true
+                }
+                "asrfastkeys" => {
+                    ret_val.asr_fast_keys.push((key.to_string(), value.to_string()));
+                    true
+                }
+                "plugins" if is_plugin_key(key) => {
+                    ret_val.plugins.push(value.to_string());
+                    true
+                }
+                _ => false,
+            };
+            if !known {
+                ret_val.unknown.push((section.to_string(), key.to_string(), value.to_string()));
+            }
+        }
+
+        Ok(ret_val)
+    }
+
+    fn parse_last_session(&mut self, key: &str, value: &str) -> bool {
+        let session = &mut self.last_session;
+        match key.to_lowercase().as_str() {
+            "callsign" => session.callsign = Some(value.to_string()),
+            "realname" => session.real_name = Some(value.to_string()),
+            "certificate" => session.certificate = Some(value.to_string()),
+            "rating" => session.rating = value.parse().ok(),
+            "facility" => session.facility = value.parse().ok(),
+            "server" => session.server = Some(value.to_string()),
+            // Recognised, but deliberately not kept
+            "password" => {}
+            _ => return false,
+        }
+        true
+    }
+
+    pub fn file(&self, file: PrfSettingsFile) -> Option<&str> {
+        self.files.get(&file).map(String::as_str)
+    }
+
+    /// The value of an unknown line, matched case-insensitively.
+    pub fn unknown_value(&self, section: &str, key: &str) -> Option<&str> {
+        self.unknown
+            .iter()
+            .find(|(sct, k, _)| sct.eq_ignore_ascii_case(section) && k.eq_ignore_ascii_case(key))
+            .map(|(_, _, value)| value.as_str())
+    }
+}
+
+/// `Plugin0`, `Plugin1`, ... but not `Plugin0Display` and the like.
+fn is_plugin_key(key: &str) -> bool {
+    key.get(..6).is_some_and(|prefix| prefix.eq_ignore_ascii_case("plugin"))
+        && key.len() > 6
+        && key[6..].chars().all(|c| c.is_ascii_digit())
+}
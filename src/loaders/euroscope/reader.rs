@@ -2,12 +2,14 @@ use std::{
     collections::HashMap,
     fs::File,
     io::{BufRead, BufReader, BufWriter},
+    sync::Arc,
     time::Instant,
 };
 
 use super::{
     colour::Colour,
     error::Error,
+    navdata::WaypointIndex,
     partial::{ArtccOrAirwayLineType, BeaconType, PartialSector, SidStarType},
     sector::Sector,
     SectorResult,
@@ -30,6 +32,12 @@ impl<R: BufRead> SctReader<R> {
         }
     }
 
+    /// Resolves names that are not defined in the sector file from `waypoint_index`.
+    pub fn with_waypoint_index(mut self, waypoint_index: Arc<WaypointIndex>) -> Self {
+        self.partial_sector.waypoint_index = Some(waypoint_index);
+        self
+    }
+
     pub fn try_read(mut self) -> SectorResult<Sector> {
         for (mut line_number, line) in self.source.lines().enumerate() {
             if let Ok(line) = line {
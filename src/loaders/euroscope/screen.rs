@@ -0,0 +1,189 @@
+use std::{fmt::Display, io::BufRead};
+
+use super::{error::Error, SectorResult};
+
+/// The EuroScope list windows.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum EsListKind {
+    Departure,
+    Arrival,
+    /// The sector inbound list.
+    Sil,
+    /// The sector exit list.
+    Sel,
+    Conflict,
+    Other(String),
+}
+impl EsListKind {
+    fn from_keyword(keyword: &str) -> Self {
+        match keyword.to_uppercase().as_str() {
+            "DEPARTURE" | "DEP" => Self::Departure,
+            "ARRIVAL" | "ARR" => Self::Arrival,
+            "SIL" => Self::Sil,
+            "SEL" => Self::Sel,
+            "CONFLICT" | "CARD" => Self::Conflict,
+            _ => Self::Other(keyword.to_string()),
+        }
+    }
+}
+impl Display for EsListKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Departure => write!(f, "departure"),
+            Self::Arrival => write!(f, "arrival"),
+            Self::Sil => write!(f, "sil"),
+            Self::Sel => write!(f, "sel"),
+            Self::Conflict => write!(f, "conflict"),
+            Self::Other(kind) => write!(f, "{}", kind.to_lowercase()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EsListColumn {
+    pub header: String,
+    /// The tag item shown in the column, e.g. `Callsign`.
+    pub item: String,
+    /// The width in characters.
+    pub width: u16,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EsList {
+    pub kind: EsListKind,
+    pub name: String,
+    /// The top left corner in screen pixels.
+    pub position: Option<(i32, i32)>,
+    pub size: Option<(u32, u32)>,
+    pub visible: bool,
+    /// The header of the column the list is sorted by, and whether it is sorted in descending order.
+    pub sort: Option<(String, bool)>,
+    pub columns: Vec<EsListColumn>,
+}
+
+/// The list windows of a EuroScope lists settings file.
+///
+/// The file is made of these lines, each with its fields separated by `:`:
+/// - `LIST:kind:name` starts a list, where the kind is `DEPARTURE`, `ARRIVAL`, `SIL`, `SEL` or `CONFLICT`.
+/// - `POSITION:x:y[:width:height]` places the list on the screen.
+/// - `VISIBLE:0` hides the list.
+/// - `SORT:header[:DESC]` sorts the list by a column.
+/// - `COLUMN:header:item:width` adds a column.
+#[derive(Debug, Clone, Default)]
+pub struct EsListSettings {
+    pub file_name: String,
+    pub lists: Vec<EsList>,
+    pub non_critical_errors: Vec<(usize, String, Error)>,
+}
+
+impl EsListSettings {
+    pub fn try_from_reader(reader: impl BufRead, file_name: &str) -> anyhow::Result<Self> {
+        let mut ret_val = EsListSettings {
+            file_name: file_name.to_string(),
+            ..Default::default()
+        };
+
+        for (line_number, line) in settings_lines(reader)? {
+            if let Err(e) = ret_val.parse_line(&line) {
+                ret_val.non_critical_errors.push((line_number, line, e));
+            }
+        }
+
+        Ok(ret_val)
+    }
+
+    pub fn list(&self, kind: &EsListKind) -> Option<&EsList> {
+        self.lists.iter().find(|list| list.kind == *kind)
+    }
+
+    fn parse_line(&mut self, line: &str) -> SectorResult<()> {
+        let sections = line.split(':').map(str::trim).collect::<Vec<_>>();
+        let keyword = sections[0].to_uppercase();
+        if keyword == "LIST" {
+            let kind = sections.get(1).filter(|kind| !kind.is_empty()).ok_or(Error::InvalidListDefinition)?;
+            self.lists.push(EsList {
+                kind: EsListKind::from_keyword(kind),
+                name: sections.get(2..).unwrap_or_default().join(":"),
+                position: None,
+                size: None,
+                visible: true,
+                sort: None,
+                columns: Vec::new(),
+            });
+            return Ok(());
+        }
+
+        let number = |index: usize| sections.get(index).and_then(|value| value.parse::<i64>().ok());
+        match keyword.as_str() {
+            "POSITION" => {
+                let list = self.current_list()?;
+                let (x, y) = number(1).zip(number(2)).ok_or(Error::InvalidListDefinition)?;
+                list.position = Some((x as i32, y as i32));
+                list.size = number(3).zip(number(4)).map(|(width, height)| (width.max(0) as u32, height.max(0) as u32));
+            }
+            "VISIBLE" => self.current_list()?.visible = sections.get(1).is_none_or(|visible| *visible != "0"),
+            "SORT" => {
+                let header = sections.get(1).filter(|header| !header.is_empty()).ok_or(Error::InvalidListDefinition)?;
+                let descending = sections.get(2).is_some_and(|order| order.eq_ignore_ascii_case("DESC"));
+                self.current_list()?.sort = Some((header.to_string(), descending));
+            }
+            "COLUMN" => {
+                let header = sections.get(1).ok_or(Error::InvalidListDefinition)?;
+                let item = sections.get(2).filter(|item| !item.is_empty()).ok_or(Error::InvalidListDefinition)?;
+                let width = number(3).map(|width| width.clamp(0, i64::from(u16::MAX)) as u16).unwrap_or_default();
+                self.current_list()?.columns.push(EsListColumn {
+                    header: header.to_string(),
+                    item: item.to_string(),
+                    width,
+                });
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn current_list(&mut self) -> SectorResult<&mut EsList> {
+        self.lists.last_mut().ok_or(Error::InvalidListDefinition)
+    }
+}
+
+/// The `key:value` options of a EuroScope screen settings file, in the order they appear.
+#[derive(Debug, Clone, Default)]
+pub struct EsScreenSettings {
+    pub file_name: String,
+    pub options: Vec<(String, String)>,
+}
+
+impl EsScreenSettings {
+    pub fn try_from_reader(reader: impl BufRead, file_name: &str) -> anyhow::Result<Self> {
+        let options = settings_lines(reader)?
+            .into_iter()
+            .filter_map(|(_, line)| {
+                let (key, value) = line.split_once(':')?;
+                Some((key.trim().to_string(), value.trim().to_string()))
+            })
+            .filter(|(key, _)| !key.is_empty())
+            .collect();
+        Ok(EsScreenSettings {
+            file_name: file_name.to_string(),
+            options,
+        })
+    }
+
+    /// The value of option `key` (case-insensitive). Later lines win.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.options.iter().rev().find(|(option, _)| option.eq_ignore_ascii_case(key)).map(|(_, value)| value.as_str())
+    }
+}
+
+/// The non-empty lines that are not `;` or `//` comments, with their line numbers.
+fn settings_lines(reader: impl BufRead) -> anyhow::Result<Vec<(usize, String)>> {
+    let mut lines = Vec::new();
+    for (line_number, line) in reader.split(b'\n').enumerate() {
+        let line = String::from_utf8_lossy(&line?).trim().to_string();
+        if !line.is_empty() && !line.starts_with(';') && !line.starts_with("//") {
+            lines.push((line_number + 1, line));
+        }
+    }
+    Ok(lines)
+}
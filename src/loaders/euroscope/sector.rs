@@ -10,7 +10,7 @@ use super::{
         PartialSector,
     },
     position::{Position, Valid},
-    waypoint::{Airport, Fix, Ndb, Vor},
+    waypoint::{Airport, Fix, Ndb, Vor, Waypoint},
 };
 
 #[derive(Debug)]
@@ -35,6 +35,20 @@ pub struct Sector {
     pub non_critical_errors: Vec<(usize, String, Error)>,
 }
 
+impl Sector {
+    /// Looks up a fix, VOR, NDB or airport by identifier (case-insensitive), in that order.
+    pub fn find_waypoint(&self, identifier: &str) -> Option<Position<Valid>> {
+        self.fixes
+            .iter()
+            .map(|fix| fix as &dyn Waypoint)
+            .chain(self.vors.iter().map(|vor| vor as &dyn Waypoint))
+            .chain(self.ndbs.iter().map(|ndb| ndb as &dyn Waypoint))
+            .chain(self.airports.iter().map(|airport| airport as &dyn Waypoint))
+            .find(|waypoint| waypoint.identifier().eq_ignore_ascii_case(identifier))
+            .map(|waypoint| waypoint.position())
+    }
+}
+
 impl TryFrom<PartialSector> for Sector {
     type Error = Error;
     fn try_from(value: PartialSector) -> Result<Self, Self::Error> {
@@ -1,6 +1,6 @@
-use std::{collections::HashMap, fs::File, io::{BufRead, BufReader}, path::Path};
+use std::{fs::File, io::{BufRead, BufReader}, path::Path};
 
-use super::colour::Colour;
+use super::{colour::Colour, error::Error, SectorResult};
 
 #[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub enum SymbologyItemType {
@@ -27,7 +27,9 @@ pub enum SymbologyItemType {
     Sector,
     GroundNetwork,
     Region,
-    Label
+    Label,
+    /// An item type this version does not know, with its name as written.
+    Unknown(String)
 }
 
 impl SymbologyItemType {
@@ -56,6 +58,7 @@ impl SymbologyItemType {
             SymbologyItemType::GroundNetwork => "ground_network".to_string(),
             SymbologyItemType::Region => "regions".to_string(),
             SymbologyItemType::Label => "free_text".to_string(),
+            SymbologyItemType::Unknown(name) => name.to_lowercase().replace(' ', "_"),
         }
     }
 }
@@ -111,64 +114,115 @@ pub struct SymbologyAttribute {
 #[derive(Debug, Default)]
 pub struct SymbologyInfo {
     pub file_name: String,
+    /// The items in the order they first appear in the file.
     pub symbols: Vec<SymbologyItem>,
     pub symbol_icons: Vec<(u8, Vec<String>)>,
-    pub clipping_area: u8
+    /// The distance in pixels around symbols and labels within which lines are not drawn, so they stay readable.
+    pub clipping_area: u8,
+    pub font_name: Option<String>,
+    pub font_size: Option<f32>,
+    pub non_critical_errors: Vec<(usize, String, Error)>
 }
 
 impl SymbologyInfo {
+    /// The clipping area EuroScope uses when the file does not set one.
+    pub const DEFAULT_CLIPPING_AREA: u8 = 5;
+
     pub fn try_from_file(symbology_file: impl AsRef<Path>) -> anyhow::Result<Self> {
-        let file_reader = BufReader::new(File::open(&symbology_file)?);
-        let mut clip_area = 5_u8;
-        let mut symbols: HashMap<String, SymbologyItem> = HashMap::new();
-        let mut symbol_icons_defs = Vec::new();
-
-        for line in file_reader.lines() {
-            if let Ok(line_str) = line {
-                let items = line_str.split(":").collect::<Vec<&str>>();
-
-                if items.len() > 0 {
-                    match items[0].to_lowercase().as_str() {
-                        "m_cliparea" => clip_area = items[1].parse()?,
-                        "symbol" => symbol_icons_defs.push((items[1].parse::<u8>()?, Vec::new())),
-                        "symbolitem" => {
-                            if let Some(symb) = symbol_icons_defs.last_mut() {
-                                symb.1.push(items[1].to_string());
-                            }
-                        },
-                        &_ => {
-                            if let Ok(symbol_type) = items[0].try_into() {
-                                // Create Symbol Def
-                                let symbol_def = SymbologyAttribute {
-                                    attribute: items[1].to_string(),
-                                    color: Colour::from(items[2].parse::<u32>()?),
-                                    size: items[3].parse()?,
-                                    line_weight: items[5].parse()?,
-                                    line_style: items[4].parse()?,
-                                    text_align: items[6].parse()?
-                                };
-
-                                // Update/Push into map
-                                if let Some(symbol_item) = symbols.get_mut(items[0]) {
-                                    symbol_item.defs.push(symbol_def);
-                                } else {
-                                    symbols.insert(items[0].to_string(), SymbologyItem {
-                                        item_type: symbol_type,
-                                        defs: vec![symbol_def]
-                                    });
-                                }
-                            }
-                        }
+        Self::try_from_reader(BufReader::new(File::open(&symbology_file)?), symbology_file)
+    }
+
+    /// Parses symbology from any reader. `symbology_file` is only recorded as the file name.
+    ///
+    /// Lines that cannot be parsed are skipped and recorded in `non_critical_errors`.
+    pub fn try_from_reader(file_reader: impl BufRead, symbology_file: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let mut ret_val = SymbologyInfo {
+            file_name: symbology_file.as_ref().to_str().unwrap_or_default().to_string(),
+            clipping_area: Self::DEFAULT_CLIPPING_AREA,
+            ..Default::default()
+        };
+        // Whether SYMBOLITEM lines belong to the last symbol icon
+        let mut in_symbol = false;
+
+        for (line_number, line) in file_reader.split(b'\n').enumerate() {
+            let line = String::from_utf8_lossy(&line?).trim().to_string();
+            if line.is_empty() {
+                continue;
+            }
+            let items = line.split(':').collect::<Vec<&str>>();
+
+            let result = match items[0].to_lowercase().as_str() {
+                "m_cliparea" => Self::parse_number(&items, 1).map(|clip_area| ret_val.clipping_area = clip_area),
+                "m_fontname" | "fontname" => {
+                    ret_val.font_name = items.get(1..).map(|name| name.join(":").trim().to_string()).filter(|name| !name.is_empty());
+                    Ok(())
+                }
+                "m_fontsize" | "fontsize" => Self::parse_number(&items, 1).map(|size| ret_val.font_size = Some(size)),
+                "symbol" => {
+                    let icon = Self::parse_number::<u8>(&items, 1);
+                    in_symbol = icon.is_ok();
+                    icon.map(|icon| ret_val.symbol_icons.push((icon, Vec::new())))
+                }
+                "symbolitem" => match ret_val.symbol_icons.last_mut() {
+                    Some(symbol) if in_symbol && items.len() > 1 => {
+                        symbol.1.push(items[1..].join(":"));
+                        Ok(())
                     }
+                    _ => Err(Error::InvalidSymbology),
+                },
+                _ => {
+                    in_symbol = false;
+                    ret_val.parse_attribute_line(&items)
                 }
+            };
+            if let Err(e) = result {
+                ret_val.non_critical_errors.push((line_number + 1, line, e));
             }
         }
 
-        Ok(SymbologyInfo {
-            file_name: symbology_file.as_ref().to_str().unwrap().to_string(),
-            clipping_area: clip_area,
-            symbols: symbols.values().cloned().collect(),
-            symbol_icons: symbol_icons_defs
-        })
+        Ok(ret_val)
+    }
+
+    /// The item of `item_type`, if the file defines it.
+    pub fn item(&self, item_type: &SymbologyItemType) -> Option<&SymbologyItem> {
+        self.symbols.iter().find(|symbol_item| symbol_item.item_type == *item_type)
+    }
+
+    /// Parses an `item:attribute:colour:size:line style:line weight:text align` line.
+    ///
+    /// Lines without `:` are headers such as `SYMBOLOGY` and are ignored.
+    fn parse_attribute_line(&mut self, items: &[&str]) -> SectorResult<()> {
+        if items.len() == 1 {
+            return Ok(());
+        }
+        if items.len() < 7 {
+            return Err(Error::InvalidSymbology);
+        }
+        let symbol_def = SymbologyAttribute {
+            attribute: items[1].to_string(),
+            color: Colour::from(Self::parse_number::<u32>(items, 2)?),
+            size: Self::parse_number(items, 3)?,
+            line_weight: Self::parse_number(items, 5)?,
+            line_style: Self::parse_number(items, 4)?,
+            text_align: Self::parse_number(items, 6)?,
+        };
+        let item_type = SymbologyItemType::try_from(items[0]).unwrap_or_else(|_| SymbologyItemType::Unknown(items[0].to_string()));
+
+        // Update/Push into list
+        match self.symbols.iter_mut().find(|symbol_item| symbol_item.item_type == item_type) {
+            Some(symbol_item) => symbol_item.defs.push(symbol_def),
+            None => self.symbols.push(SymbologyItem {
+                item_type,
+                defs: vec![symbol_def],
+            }),
+        }
+        Ok(())
     }
-}
\ No newline at end of file
+
+    fn parse_number<T: std::str::FromStr>(items: &[&str], index: usize) -> SectorResult<T> {
+        items
+            .get(index)
+            .and_then(|item| item.trim().parse().ok())
+            .ok_or(Error::InvalidSymbology)
+    }
+}
@@ -0,0 +1,141 @@
+use std::io::BufRead;
+
+use super::{colour::Colour, error::Error, SectorResult};
+
+/// One item of a tag line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EsTagItem {
+    /// The item shown, e.g. `Callsign` or `Assigned Squawk`.
+    pub item_type: String,
+    /// `None` to use the colour of the aircraft's state from the `Datablock` symbology.
+    pub colour: Option<Colour>,
+    pub bold: bool,
+    /// The function run when the item is left clicked, e.g. `Open FP dialog`.
+    pub left_click: Option<String>,
+    pub right_click: Option<String>,
+}
+
+/// The layout of one tag type in a family, e.g. the detailed tag of assumed aircraft.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EsTagDefinition {
+    /// The aircraft state the tag is for, e.g. `Untagged`, `Tagged` or `Assumed`.
+    pub tag_type: String,
+    /// Whether this is the detailed tag, shown when the mouse is over the aircraft.
+    pub detailed: bool,
+    pub lines: Vec<Vec<EsTagItem>>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EsTagFamily {
+    pub name: String,
+    pub definitions: Vec<EsTagDefinition>,
+}
+impl EsTagFamily {
+    pub fn definition(&self, tag_type: &str, detailed: bool) -> Option<&EsTagDefinition> {
+        self.definitions
+            .iter()
+            .find(|definition| definition.detailed == detailed && definition.tag_type.eq_ignore_ascii_case(tag_type))
+    }
+}
+
+/// The tag families of a EuroScope tags settings file.
+///
+/// The file is made of these lines, each with its fields separated by `:`:
+/// - `TAGFAMILY:name` starts a family.
+/// - `TAGTYPE:type[:DETAILED]` starts the compact or detailed tag of an aircraft state in the family.
+/// - `TAGITEM:item[:colour[:bold[:left click[:right click]]]]` adds an item to the current line of the tag. The colour
+///   is a EuroScope colour number, or `0` or empty for the state colour.
+/// - `TAGLINE` ends the current line of the tag.
+#[derive(Debug, Clone, Default)]
+pub struct EsTags {
+    pub file_name: String,
+    pub families: Vec<EsTagFamily>,
+    pub non_critical_errors: Vec<(usize, String, Error)>,
+}
+
+impl EsTags {
+    pub fn try_from_reader(reader: impl BufRead, file_name: &str) -> anyhow::Result<Self> {
+        let mut ret_val = EsTags {
+            file_name: file_name.to_string(),
+            ..Default::default()
+        };
+
+        for (line_number, line) in reader.split(b'\n').enumerate() {
+            let line = String::from_utf8_lossy(&line?).trim().to_string();
+            if line.is_empty() || line.starts_with(';') || line.starts_with("//") {
+                continue;
+            }
+            if let Err(e) = ret_val.parse_line(&line) {
+                ret_val.non_critical_errors.push((line_number + 1, line, e));
+            }
+        }
+
+        // A tag without a final TAGLINE still has its last line
+        for definition in ret_val.families.iter_mut().flat_map(|family| family.definitions.iter_mut()) {
+            if definition.lines.last().is_some_and(|line| line.is_empty()) {
+                definition.lines.pop();
+            }
+        }
+
+        Ok(ret_val)
+    }
+
+    pub fn family(&self, name: &str) -> Option<&EsTagFamily> {
+        self.families.iter().find(|family| family.name.eq_ignore_ascii_case(name))
+    }
+
+    fn parse_line(&mut self, line: &str) -> SectorResult<()> {
+        let sections = line.split(':').map(str::trim).collect::<Vec<_>>();
+        match sections[0].to_uppercase().as_str() {
+            "TAGFAMILY" => {
+                let name = sections.get(1..).unwrap_or_default().join(":");
+                if name.is_empty() {
+                    return Err(Error::InvalidTagDefinition);
+                }
+                self.families.push(EsTagFamily {
+                    name,
+                    definitions: Vec::new(),
+                });
+            }
+            "TAGTYPE" => {
+                let family = self.families.last_mut().ok_or(Error::InvalidTagDefinition)?;
+                let tag_type = sections.get(1).filter(|tag_type| !tag_type.is_empty()).ok_or(Error::InvalidTagDefinition)?;
+                family.definitions.push(EsTagDefinition {
+                    tag_type: tag_type.to_string(),
+                    detailed: sections.get(2).is_some_and(|detailed| detailed.eq_ignore_ascii_case("DETAILED") || *detailed == "1"),
+                    lines: vec![Vec::new()],
+                });
+            }
+            "TAGITEM" => {
+                let item = Self::parse_item(&sections)?;
+                self.current_definition()?.lines.last_mut().ok_or(Error::InvalidTagDefinition)?.push(item);
+            }
+            "TAGLINE" => self.current_definition()?.lines.push(Vec::new()),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn parse_item(sections: &[&str]) -> SectorResult<EsTagItem> {
+        let item_type = sections.get(1).filter(|item_type| !item_type.is_empty()).ok_or(Error::InvalidTagDefinition)?;
+        let colour = match sections.get(2) {
+            None | Some(&"") | Some(&"0") => None,
+            Some(colour) => Some(colour.parse::<Colour>()?),
+        };
+        let function = |index: usize| sections.get(index).filter(|function| !function.is_empty()).map(|function| function.to_string());
+        Ok(EsTagItem {
+            item_type: item_type.to_string(),
+            colour,
+            bold: sections.get(3).is_some_and(|bold| *bold == "1"),
+            left_click: function(4),
+            right_click: function(5),
+        })
+    }
+
+    fn current_definition(&mut self) -> SectorResult<&mut EsTagDefinition> {
+        self.families
+            .last_mut()
+            .and_then(|family| family.definitions.last_mut())
+            .ok_or(Error::InvalidTagDefinition)
+    }
+}
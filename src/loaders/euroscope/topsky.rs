@@ -0,0 +1,322 @@
+use std::{collections::{BTreeMap, HashMap}, fmt::Display, io::BufRead};
+
+use aviation_calc_util::{
+    geo::{Bearing, GeoPoint},
+    units::Length,
+};
+
+use super::{
+    colour::Colour,
+    error::Error,
+    position::{Position, Valid},
+    sector::Sector,
+    SectorResult,
+};
+
+/// Degrees between the points of a `CIRCLE` that does not give its own step.
+const DEFAULT_CIRCLE_STEP: f64 = 10.0;
+/// The smallest `CIRCLE` step in degrees, which limits a circle to 3600 points.
+const MIN_CIRCLE_STEP: f64 = 0.1;
+
+/// A condition from an `ACTIVE` line. A map with several conditions is shown when any of them is met.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TopSkyActive {
+    /// `ACTIVE:1`
+    Always,
+    /// `ACTIVE:RWY:ARR:EGLL27R,EGLL27L:DEP:*`, where `*` matches any runway.
+    Runway { arrival: Vec<String>, departure: Vec<String> },
+    /// Conditions that depend on the session, such as `ID`, `SCHED` or `NOTAM`.
+    Other { kind: String, value: String },
+}
+impl TopSkyActive {
+    fn from_value(value: &str) -> Self {
+        let (kind, rest) = value.split_once(':').unwrap_or((value, ""));
+        match kind.to_uppercase().as_str() {
+            "1" => Self::Always,
+            "RWY" => {
+                let sections = rest.split(':').map(str::trim).collect::<Vec<_>>();
+                let runways = |name: &str| {
+                    sections
+                        .iter()
+                        .position(|section| section.eq_ignore_ascii_case(name))
+                        .and_then(|index| sections.get(index + 1))
+                        .map(|runways| runways.split(',').map(str::trim).filter(|runway| !runway.is_empty()).map(str::to_owned).collect())
+                        .unwrap_or_default()
+                };
+                Self::Runway {
+                    arrival: runways("ARR"),
+                    departure: runways("DEP"),
+                }
+            }
+            _ => Self::Other {
+                kind: kind.to_string(),
+                value: rest.to_string(),
+            },
+        }
+    }
+}
+impl Display for TopSkyActive {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Always => write!(f, "1"),
+            Self::Runway { arrival, departure } => write!(f, "RWY:ARR:{}:DEP:{}", arrival.join(","), departure.join(",")),
+            Self::Other { kind, value } if value.is_empty() => write!(f, "{}", kind),
+            Self::Other { kind, value } => write!(f, "{}:{}", kind, value),
+        }
+    }
+}
+
+/// A drawn item of a TopSky map. Colours are the names given by the last `COLOR` line.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TopSkyMapItem {
+    /// A `LINE`, a `COORDLINE` or a `CIRCLE`.
+    Line { points: Vec<Position<Valid>>, colour: Option<String> },
+    /// A `COORDPOLY`, with its fill in percent.
+    Polygon { points: Vec<Position<Valid>>, colour: Option<String>, fill: u8 },
+    Text { position: Position<Valid>, text: String, colour: Option<String> },
+    Symbol { symbol: String, position: Position<Valid>, label: Option<String>, colour: Option<String> },
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TopSkyMap {
+    pub name: String,
+    /// The `FOLDER` the map is listed in, empty if it has none.
+    pub folder: String,
+    pub active: Vec<TopSkyActive>,
+    pub items: Vec<TopSkyMapItem>,
+}
+impl TopSkyMap {
+    pub fn is_always_active(&self) -> bool {
+        self.active.contains(&TopSkyActive::Always)
+    }
+}
+
+/// TopSky colours, looked up case-insensitively by name.
+#[derive(Debug, Clone, Default)]
+pub struct TopSkyColours {
+    colours: HashMap<String, Colour>,
+}
+impl TopSkyColours {
+    pub fn insert(&mut self, name: &str, colour: Colour) {
+        self.colours.insert(name.to_lowercase(), colour);
+    }
+
+    pub fn get(&self, name: &str) -> Option<Colour> {
+        self.colours.get(&name.to_lowercase()).copied()
+    }
+
+    /// Adds the `Color_Name=R,G,B` lines of a TopSky settings file, replacing colours of the same name.
+    pub fn try_load_settings(&mut self, reader: impl BufRead) -> anyhow::Result<()> {
+        for line in reader.split(b'\n') {
+            let line = String::from_utf8_lossy(&line?).to_string();
+            let Some((key, value)) = line.trim().split_once('=') else {
+                continue;
+            };
+            let Some(name) = key.trim().strip_prefix("Color_") else {
+                continue;
+            };
+            if let Some(colour) = parse_rgb(&value.split(',').collect::<Vec<_>>()) {
+                self.insert(name, colour);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The maps of a `TopSkyMaps.txt` file.
+///
+/// Positions are either ES coordinates (`N051.28.39.000:W000.27.41.000`) or the name of a waypoint in the sector.
+#[derive(Debug, Default)]
+pub struct TopSkyMaps {
+    pub file_name: String,
+    pub maps: Vec<TopSkyMap>,
+    /// The `COLORDEF` colours of the file, followed by those of the TopSky settings once they are loaded.
+    pub colours: TopSkyColours,
+    pub non_critical_errors: Vec<(usize, String, Error)>,
+}
+
+impl TopSkyMaps {
+    pub fn try_from_reader(reader: impl BufRead, file_name: &str, sector: Option<&Sector>) -> anyhow::Result<Self> {
+        let mut ret_val = TopSkyMaps {
+            file_name: file_name.to_string(),
+            ..Default::default()
+        };
+        let mut parser = TopSkyMapsParser {
+            sector,
+            in_map: false,
+            colour: None,
+            coords: Vec::new(),
+        };
+
+        for (line_number, line) in reader.split(b'\n').enumerate() {
+            let line = String::from_utf8_lossy(&line?).trim().to_string();
+            if line.is_empty() || line.starts_with("//") {
+                continue;
+            }
+            if let Err(e) = parser.parse_line(&mut ret_val, &line) {
+                ret_val.non_critical_errors.push((line_number + 1, line, e));
+            }
+        }
+
+        Ok(ret_val)
+    }
+
+    /// The maps of each folder, by folder name.
+    pub fn folders(&self) -> BTreeMap<&str, Vec<&TopSkyMap>> {
+        let mut ret_val: BTreeMap<&str, Vec<&TopSkyMap>> = BTreeMap::new();
+        for map in &self.maps {
+            ret_val.entry(map.folder.as_str()).or_default().push(map);
+        }
+        ret_val
+    }
+}
+
+struct TopSkyMapsParser<'a> {
+    sector: Option<&'a Sector>,
+    /// Whether lines are part of a `MAP`, as opposed to a `SYMBOLDEF` or the definitions at the start of the file.
+    in_map: bool,
+    colour: Option<String>,
+    /// The `COORD` points waiting for a `COORDLINE` or `COORDPOLY`.
+    coords: Vec<Position<Valid>>,
+}
+
+impl TopSkyMapsParser<'_> {
+    fn parse_line(&mut self, maps: &mut TopSkyMaps, line: &str) -> SectorResult<()> {
+        let (keyword, value) = line.split_once(':').unwrap_or((line, ""));
+        let sections = value.split(':').map(str::trim).collect::<Vec<_>>();
+        match keyword.trim().to_uppercase().as_str() {
+            "COLORDEF" => {
+                self.in_map = false;
+                let colour = sections.get(1..).and_then(parse_rgb).ok_or(Error::InvalidColourDefinition)?;
+                maps.colours.insert(sections[0], colour);
+            }
+            "SYMBOLDEF" => self.in_map = false,
+            "MAP" => {
+                self.in_map = true;
+                self.colour = None;
+                self.coords.clear();
+                maps.maps.push(TopSkyMap {
+                    name: value.trim().to_string(),
+                    ..Default::default()
+                });
+            }
+            keyword if self.in_map => {
+                let map = maps.maps.last_mut().ok_or(Error::InvalidTopSkyMap)?;
+                self.parse_map_line(map, keyword, value, &sections)?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn parse_map_line(&mut self, map: &mut TopSkyMap, keyword: &str, value: &str, sections: &[&str]) -> SectorResult<()> {
+        match keyword {
+            "FOLDER" => map.folder = value.trim().to_string(),
+            "COLOR" => self.colour = sections.first().filter(|colour| !colour.is_empty()).map(|colour| colour.to_string()),
+            "ACTIVE" => map.active.push(TopSkyActive::from_value(value.trim())),
+            "LINE" => {
+                let (start, used) = self.parse_point(sections)?;
+                let (end, _) = self.parse_point(&sections[used..])?;
+                map.items.push(TopSkyMapItem::Line {
+                    points: vec![start, end],
+                    colour: self.colour.clone(),
+                });
+            }
+            "COORD" => {
+                let (point, _) = self.parse_point(sections)?;
+                self.coords.push(point);
+            }
+            "COORDLINE" => {
+                let points = std::mem::take(&mut self.coords);
+                if points.len() < 2 {
+                    return Err(Error::InvalidTopSkyMap);
+                }
+                map.items.push(TopSkyMapItem::Line {
+                    points,
+                    colour: self.colour.clone(),
+                });
+            }
+            "COORDPOLY" => {
+                let points = std::mem::take(&mut self.coords);
+                if points.len() < 3 {
+                    return Err(Error::InvalidTopSkyMap);
+                }
+                map.items.push(TopSkyMapItem::Polygon {
+                    points,
+                    colour: self.colour.clone(),
+                    fill: sections.first().and_then(|fill| fill.parse::<u8>().ok()).unwrap_or(100).min(100),
+                });
+            }
+            "CIRCLE" => {
+                let (centre, used) = self.parse_point(sections)?;
+                let radius = sections.get(used).and_then(|radius| radius.parse::<f64>().ok()).ok_or(Error::InvalidTopSkyMap)?;
+                let step = sections
+                    .get(used + 1)
+                    .and_then(|step| step.parse::<f64>().ok())
+                    .filter(|step| *step > 0.0)
+                    .unwrap_or(DEFAULT_CIRCLE_STEP);
+                if step < MIN_CIRCLE_STEP {
+                    return Err(Error::InvalidTopSkyMap);
+                }
+                map.items.push(TopSkyMapItem::Line {
+                    points: circle(centre, radius, step)?,
+                    colour: self.colour.clone(),
+                });
+            }
+            "TEXT" => {
+                let (position, used) = self.parse_point(sections)?;
+                map.items.push(TopSkyMapItem::Text {
+                    position,
+                    text: sections.get(used..).unwrap_or_default().join(":"),
+                    colour: self.colour.clone(),
+                });
+            }
+            "SYMBOL" => {
+                let symbol = sections.first().filter(|symbol| !symbol.is_empty()).ok_or(Error::InvalidTopSkyMap)?;
+                let (position, used) = self.parse_point(&sections[1..])?;
+                map.items.push(TopSkyMapItem::Symbol {
+                    symbol: symbol.to_string(),
+                    position,
+                    label: sections.get(1 + used).filter(|label| !label.is_empty()).map(|label| label.to_string()),
+                    colour: self.colour.clone(),
+                });
+            }
+            // Display settings such as ZOOM, LAYER and STYLE
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// The position at the start of `sections` and the number of sections it used.
+    fn parse_point(&self, sections: &[&str]) -> SectorResult<(Position<Valid>, usize)> {
+        if let [lat, lon, ..] = sections {
+            if let Ok(position) = Position::try_new_from_es(lat, lon) {
+                return Ok((position.validate()?, 2));
+            }
+        }
+        let name = sections.first().ok_or(Error::InvalidPosition)?;
+        self.sector
+            .and_then(|sector| sector.find_waypoint(name))
+            .map(|position| (position, 1))
+            .ok_or(Error::InvalidPosition)
+    }
+}
+
+fn parse_rgb(sections: &[&str]) -> Option<Colour> {
+    let [r, g, b] = sections.get(..3)? else {
+        return None;
+    };
+    Some(Colour::new(r.trim().parse().ok()?, g.trim().parse().ok()?, b.trim().parse().ok()?))
+}
+
+/// A closed circle of `radius` nautical miles, with a point every `step` degrees.
+fn circle(centre: Position<Valid>, radius: f64, step: f64) -> SectorResult<Vec<Position<Valid>>> {
+    let count = (360.0 / step).ceil() as usize;
+    (0..=count)
+        .map(|i| {
+            let mut point = GeoPoint::from_degs_and_ft(centre.lat, centre.lon, 0_f64);
+            point.move_by(Bearing::from_degrees((i as f64 * step).min(360.0)), Length::from_nautical_miles(radius));
+            Position::new(point.lat.as_degrees(), point.lon.as_degrees()).validate()
+        })
+        .collect()
+}
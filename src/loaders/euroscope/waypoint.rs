@@ -87,6 +87,12 @@ impl Airport {
     pub fn airspace_class(&self) -> AirspaceClass {
         self.airspace_class
     }
+    pub fn runway_end(&self, number: u8, modifier: &RunwayModifier) -> Option<&RunwayEnd> {
+        self.runways
+            .iter()
+            .flat_map(|strip| [&strip.end_a, &strip.end_b])
+            .find(|end| end.number == number && &end.modifier == modifier)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -52,10 +52,10 @@ pub struct CrcPositionStarsConfig {
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct BeaconCodeBank {
-    id: String,
-    category: Option<String>,
-    priority: Option<String>,
-    subset: Option<u32>,
-    start: u32,
-    end: u32
+    pub id: String,
+    pub category: Option<String>,
+    pub priority: Option<String>,
+    pub subset: Option<u32>,
+    pub start: u32,
+    pub end: u32
 }
\ No newline at end of file
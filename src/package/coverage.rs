@@ -0,0 +1,127 @@
+use aviation_calc_util::{
+    geo::{Bearing, GeoPoint, EARTH_RADIUS},
+    units::{Angle, Length},
+};
+
+use crate::loaders::{
+    ese::radar::{RadarCoverage, RadarSite},
+    vnas_crc::{eram::AsrSite, CrcTranceiver},
+};
+
+/// Step between the vertices of a coverage ring, in degrees.
+const RING_STEP_DEGREES: usize = 5;
+
+/// A radar head or radio antenna that coverage can be computed for.
+///
+/// Coverage is limited by the radio horizon under the 4/3 earth radius model and, where given, by `range`,
+/// `ceiling` and a cone of silence above the antenna.
+#[derive(Debug, Clone)]
+pub struct CoverageSource {
+    pub name: String,
+    pub location: GeoPoint,
+    /// Antenna height above mean sea level.
+    pub antenna_height: Length,
+    pub range: Option<Length>,
+    pub ceiling: Option<Length>,
+    pub cone_slope: Option<Angle>,
+}
+
+/// The coverage of a [`CoverageSource`] at a single level, as a ring around the antenna.
+#[derive(Debug, Clone, Copy)]
+pub struct CoverageRing {
+    pub flight_level: u32,
+    pub inner_radius: Length,
+    pub outer_radius: Length,
+}
+
+impl CoverageSource {
+    /// One source per mode (primary, Mode-S, Mode-A/C) fitted to the ESE radar site.
+    pub fn from_es_radar_site(site: &RadarSite) -> Vec<Self> {
+        [("PSR", &site.primary), ("Mode-S", &site.mode_s), ("SSR", &site.secondary)]
+            .into_iter()
+            .filter_map(|(mode_name, coverage)| coverage.map(|coverage| Self::from_es_radar_mode(site, mode_name, coverage)))
+            .collect()
+    }
+
+    fn from_es_radar_mode(site: &RadarSite, mode_name: &str, coverage: RadarCoverage) -> Self {
+        CoverageSource {
+            name: format!("{} {}", site.name, mode_name),
+            location: GeoPoint::from_degs_and_ft(site.position.lat, site.position.lon, 0_f64),
+            antenna_height: Length::from_feet(site.height.into()),
+            range: Some(Length::from_nautical_miles(coverage.range.into())),
+            ceiling: (coverage.altitude > 0).then(|| Length::from_feet(coverage.altitude.into())),
+            cone_slope: (coverage.cone_slope > 0_f64).then(|| Angle::from_degrees(coverage.cone_slope)),
+        }
+    }
+
+    /// CRC ASR sites have no antenna height and the altitude of their location is often 0, so it is given as `antenna_height`.
+    pub fn from_crc_asr_site(site: &AsrSite, antenna_height: Length) -> Self {
+        CoverageSource {
+            name: site.asr_id.to_string(),
+            location: site.location,
+            antenna_height,
+            range: Some(Length::from_nautical_miles(site.range.into())),
+            ceiling: (site.ceiling > 0).then(|| Length::from_feet(site.ceiling.into())),
+            cone_slope: None,
+        }
+    }
+
+    pub fn from_crc_tranceiver(tranceiver: &CrcTranceiver) -> Self {
+        CoverageSource {
+            name: tranceiver.name.to_string(),
+            location: tranceiver.location,
+            antenna_height: Length::from_meters(tranceiver.height_msl_meters),
+            range: None,
+            ceiling: None,
+            cone_slope: None,
+        }
+    }
+
+    /// Distance to the radio horizon between the antenna and a target at `altitude`, using the 4/3 earth radius model.
+    pub fn line_of_sight_range(&self, altitude: Length) -> Length {
+        let effective_radius = EARTH_RADIUS.as_meters() * 4_f64 / 3_f64;
+        let antenna = self.antenna_height.as_meters().max(0_f64);
+        let target = altitude.as_meters().max(0_f64);
+        Length::from_meters((2_f64 * effective_radius * antenna).sqrt() + (2_f64 * effective_radius * target).sqrt())
+    }
+
+    /// The coverage ring at `flight_level`, or `None` if the level is not covered at all.
+    pub fn coverage_ring(&self, flight_level: u32) -> Option<CoverageRing> {
+        let altitude = Length::from_feet(f64::from(flight_level) * 100_f64);
+        if self.ceiling.is_some_and(|ceiling| altitude.as_meters() > ceiling.as_meters()) {
+            return None;
+        }
+
+        let mut outer_radius = self.line_of_sight_range(altitude);
+        if let Some(range) = self.range {
+            outer_radius = Length::from_meters(outer_radius.as_meters().min(range.as_meters()));
+        }
+
+        let inner_radius = match self.cone_slope {
+            Some(slope) => {
+                let height_above_antenna = (altitude.as_meters() - self.antenna_height.as_meters()).max(0_f64);
+                Length::from_meters(height_above_antenna / slope.as_radians().tan())
+            }
+            None => Length::from_meters(0_f64),
+        };
+
+        (inner_radius.as_meters() < outer_radius.as_meters()).then_some(CoverageRing {
+            flight_level,
+            inner_radius,
+            outer_radius,
+        })
+    }
+
+    /// Vertices (`[lon, lat]`) of a closed circle of `radius` around the antenna.
+    pub(crate) fn circle(&self, radius: Length) -> Vec<Vec<f64>> {
+        (0..=360)
+            .step_by(RING_STEP_DEGREES)
+            .map(|bearing| {
+                let mut point = self.location;
+                point.alt = Length::from_meters(0_f64);
+                point.move_by(Bearing::from_degrees(bearing as f64), radius);
+                vec![point.lon.as_degrees(), point.lat.as_degrees()]
+            })
+            .collect()
+    }
+}
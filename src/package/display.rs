@@ -1,12 +1,13 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::ops::Deref;
 use aviation_calc_util::{geo::{Bearing, GeoPoint}, units::{Angle, Length}};
 use geojson::{Feature, FeatureCollection, Geometry, Value};
 use serde::{Deserialize, Serialize};
 use serde_json::Map;
 
-use crate::loaders::euroscope::{colour::Colour, line::{ColouredLine, LineGroup}, sector::RegionGroup, symbology::{self, SymbologyInfo, SymbologyItemType}, EsAsr};
+use crate::loaders::euroscope::{colour::Colour, line::{ColouredLine, LineGroup}, sector::RegionGroup, symbology::{self, SymbologyInfo, SymbologyItemType}, tags::EsTags, EsAsr};
 use crate::loaders::euroscope::partial::SidStarType::Star;
+use crate::loaders::ese::FreeTextGroup;
 use crate::loaders::vnas_crc::CrcVideoMapRef;
 use crate::loaders::vnas_crc::eram::EramConfig;
 use crate::loaders::vnas_crc::stars::{StarsArea, StarsConfiguration};
@@ -20,6 +21,15 @@ pub enum AtcDisplayItem {
     NavdataItem{symbol_type: String, ident: String, show_symbol: bool, show_label: bool},
 }
 
+/// An ASR entry that sets an option of the display instead of showing an item.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AtcDisplaySetting {
+    /// An attribute shown for a named entry, e.g. the METAR of `EGLL` or a controller's frequency.
+    Item{item_type: String, name: String, attribute: String},
+    /// A setting kept by a plugin, e.g. a map TopSky shows.
+    Plugin{plugin: String, key: String, value: String},
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[repr(u8)]
 pub enum TextAlign {
@@ -68,6 +78,67 @@ pub enum AtcDisplayBackground {
     Color(String),
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatablockItem {
+    pub item_type: String,
+    /// `None` to use the colour of the aircraft's state from [`DatablockConfig::colors`].
+    pub color: Option<Colour>,
+    pub bold: bool,
+    pub left_click: Option<String>,
+    pub right_click: Option<String>,
+}
+
+/// The lines of the datablock of one aircraft state, e.g. the detailed datablock of assumed aircraft.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatablockLayout {
+    pub family: String,
+    pub tag_type: String,
+    pub detailed: bool,
+    pub lines: Vec<Vec<DatablockItem>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DatablockConfig {
+    /// Colours by aircraft state, e.g. `assumed` or `non-concerned`.
+    pub colors: HashMap<String, Colour>,
+    pub layouts: Vec<DatablockLayout>,
+}
+
+impl DatablockConfig {
+    pub fn layout(&self, family: &str, tag_type: &str, detailed: bool) -> Option<&DatablockLayout> {
+        self.layouts.iter().find(|layout| {
+            layout.detailed == detailed && layout.family.eq_ignore_ascii_case(family) && layout.tag_type.eq_ignore_ascii_case(tag_type)
+        })
+    }
+
+    pub fn add_es_tags(&mut self, tags: EsTags) {
+        for family in tags.families {
+            for definition in family.definitions {
+                self.layouts.push(DatablockLayout {
+                    family: family.name.to_string(),
+                    tag_type: definition.tag_type,
+                    detailed: definition.detailed,
+                    lines: definition
+                        .lines
+                        .into_iter()
+                        .map(|line| {
+                            line.into_iter()
+                                .map(|item| DatablockItem {
+                                    item_type: item.item_type,
+                                    color: item.colour,
+                                    bold: item.bold,
+                                    left_click: item.left_click,
+                                    right_click: item.right_click,
+                                })
+                                .collect()
+                        })
+                        .collect(),
+                });
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct AtcDisplayType {
     pub id: String,
@@ -75,7 +146,10 @@ pub struct AtcDisplayType {
     pub symbol_defaults:  HashMap<String, (DisplayDefaultConfig, DisplayDefaultConfig)>,
     pub symbol_icons: HashMap<String, SymbolIcon>,
     pub line_types: HashMap<String, Vec<u8>>,
-    pub background: AtcDisplayBackground
+    pub background: AtcDisplayBackground,
+    /// Missing from packages exported before datablocks were added.
+    #[serde(default)]
+    pub datablock: DatablockConfig
 }
 
 impl AtcDisplayType {
@@ -84,6 +158,7 @@ impl AtcDisplayType {
         let mut symbol_defaults = HashMap::new();
         let mut symbol_icons = HashMap::new();
         let mut background = AtcDisplayBackground::Blank;
+        let mut datablock = DatablockConfig::default();
 
         for symbol in symbology.symbols {
             if matches!(symbol.item_type, SymbologyItemType::Airports | SymbologyItemType::Fixes | SymbologyItemType::Vors | SymbologyItemType::Ndbs) {
@@ -119,6 +194,10 @@ impl AtcDisplayType {
                 }
 
                 map_defaults.insert(symbol.item_type.to_key_string(), cfg);
+            } else if symbol.item_type == SymbologyItemType::Datablock {
+                for attr in symbol.defs {
+                    datablock.colors.insert(attr.attribute, attr.color);
+                }
             } else if symbol.item_type == SymbologyItemType::Sector {
                 for attr in symbol.defs {
                     if attr.attribute == "active sector background" {
@@ -140,7 +219,8 @@ impl AtcDisplayType {
             symbol_defaults,
             symbol_icons,
             line_types: Self::get_es_line_types(),
-            background: background
+            background: background,
+            datablock
         })
     }
 
@@ -172,7 +252,10 @@ pub struct AtcDisplay {
     pub screen_height: Length,
     pub rotation: Angle,
     pub display_items: Vec<AtcDisplayItem>,
-    pub display_type: String    
+    pub display_type: String,
+    /// Missing from packages exported before display settings were added.
+    #[serde(default)]
+    pub settings: Vec<AtcDisplaySetting>
 }
 
 impl AtcDisplay {
@@ -195,48 +278,71 @@ impl AtcDisplay {
 
         let mut items = Vec::new();
         let mut symbols_map = HashMap::<String, usize>::new();
+        let mut navdata_map = HashMap::<(String, String), usize>::new();
+        let mut loaded_maps = HashSet::new();
+        let mut settings = Vec::new();
 
         let sector_id = value.sector_file_id.clone().unwrap_or(default_sector_id.to_string());
 
-        let mut loaded_freetexts= HashMap::new();
-
         for item in value.display_items {
-            if matches!(item.item_type, SymbologyItemType::Airports | SymbologyItemType::Fixes | SymbologyItemType::Ndbs | SymbologyItemType::Vors) {
-                let ident = format!("{}_{}_{}", sector_id.to_string(), item.item_type.to_key_string(), item.name);
-
-                let symbol_opt = match symbols_map.get_mut(&ident) {
-                    Some(symb_index) => items.get_mut(*symb_index),
-                    None => {
-                        let symb_index = items.len();
-                        symbols_map.insert(ident.to_string(), symb_index);
-                        items.push(AtcDisplayItem::Symbol { id: ident.to_string(), show_label: false, show_symbol: false});
-                        items.get_mut(symb_index)
-                    }
-                };
-                if let Some(symbol) = symbol_opt {
-                    if let AtcDisplayItem::Symbol { id, show_symbol, show_label } = symbol {
+            let map_id = format!("{}_{}_{}", sector_id, item.item_type.to_key_string(), item.name);
+            match &item.item_type {
+                SymbologyItemType::Airports => {
+                    let symb_index = *symbols_map.entry(map_id.to_string()).or_insert_with(|| {
+                        items.push(AtcDisplayItem::Symbol { id: map_id.to_string(), show_label: false, show_symbol: false });
+                        items.len() - 1
+                    });
+                    if let Some(AtcDisplayItem::Symbol { show_symbol, show_label, .. }) = items.get_mut(symb_index) {
                         if item.attribute == "symbol" {
                             *show_symbol = true;
                         } else if item.attribute == "name" {
                             *show_label = true;
                         }
-                    }                    
+                    }
                 }
-            } else if matches!(item.item_type, SymbologyItemType::ArtccBoundary | SymbologyItemType::ArtccHighBoundary | SymbologyItemType::ArtccLowBoundary | SymbologyItemType::Geo | SymbologyItemType::HighAirways | SymbologyItemType::LowAirways | SymbologyItemType::Region | SymbologyItemType::Sids | SymbologyItemType::Stars) {
-                items.push(AtcDisplayItem::Map { id: format!("{}_{}_{}", sector_id.to_string(), item.item_type.to_key_string(), item.name), visible: true })
-            } else if matches!(item.item_type, SymbologyItemType::Label) {
-                if item.attribute == "freetext" {
-                    let name_split = item.name.split("\\").collect::<Vec<&str>>();
-                    if name_split.len() >= 1 {
-                        if !loaded_freetexts.contains_key(&name_split[0].to_string()) {
-                            items.push(AtcDisplayItem::Map {id: format!("{}_{}_{}", sector_id.to_string(), item.item_type.to_key_string(), name_split[0].to_string()), visible: true});
-                            loaded_freetexts.insert(name_split[0].to_string(), ());
+                SymbologyItemType::Fixes | SymbologyItemType::Ndbs | SymbologyItemType::Vors | SymbologyItemType::Runways => {
+                    // Runway centrelines are the runway's symbol and extended centrelines are shown as their own item
+                    let (symbol_type, is_symbol) = match (&item.item_type, item.attribute.as_str()) {
+                        (SymbologyItemType::Runways, "extended centerline") => ("runway_extended_centerlines".to_string(), true),
+                        (SymbologyItemType::Runways, attribute) => (item.item_type.to_key_string(), attribute == "centerline"),
+                        (_, attribute) => (item.item_type.to_key_string(), attribute == "symbol"),
+                    };
+                    if !is_symbol && item.attribute != "name" {
+                        settings.push(AtcDisplaySetting::Item { item_type: item.item_type.to_key_string(), name: item.name, attribute: item.attribute });
+                        continue;
+                    }
+                    let navdata_index = *navdata_map.entry((symbol_type.to_string(), item.name.to_string())).or_insert_with(|| {
+                        items.push(AtcDisplayItem::NavdataItem { symbol_type, ident: item.name.to_string(), show_symbol: false, show_label: false });
+                        items.len() - 1
+                    });
+                    if let Some(AtcDisplayItem::NavdataItem { show_symbol, show_label, .. }) = items.get_mut(navdata_index) {
+                        if is_symbol {
+                            *show_symbol = true;
+                        } else {
+                            *show_label = true;
                         }
                     }
                 }
+                SymbologyItemType::ArtccBoundary | SymbologyItemType::ArtccHighBoundary | SymbologyItemType::ArtccLowBoundary | SymbologyItemType::Geo | SymbologyItemType::HighAirways | SymbologyItemType::LowAirways | SymbologyItemType::Region | SymbologyItemType::Sids | SymbologyItemType::Stars | SymbologyItemType::Sector => {
+                    // SID and STAR names and lines are drawn by the same map
+                    if loaded_maps.insert(map_id.to_string()) {
+                        items.push(AtcDisplayItem::Map { id: map_id, visible: true });
+                    }
+                }
+                SymbologyItemType::Label if item.attribute == "freetext" => {
+                    let group = FreeTextGroup::normalise_name(FreeTextGroup::group_of_asr_item(&item.name));
+                    let group_id = format!("{}_{}_{}", sector_id, item.item_type.to_key_string(), group);
+                    if loaded_maps.insert(group_id.to_string()) {
+                        items.push(AtcDisplayItem::Map { id: group_id, visible: true });
+                    }
+                }
+                // Datablock, controller and METAR entries, and any item types that are not drawn from the package
+                _ => settings.push(AtcDisplaySetting::Item { item_type: item.item_type.to_key_string(), name: item.name, attribute: item.attribute }),
             }
         }
 
+        settings.extend(value.plugin_items.into_iter().map(|item| AtcDisplaySetting::Plugin { plugin: item.plugin, key: item.key, value: item.value }));
+        ret_val.settings = settings;
         ret_val.display_items = items;
 
         ret_val
@@ -249,7 +355,8 @@ impl AtcDisplay {
             center: twr_cfg.tower_location.unwrap_or_default(),
             screen_height: Length::from_feet(f64::from(twr_cfg.default_zoom_range) * 200_f64),
             rotation: Angle::from_degrees(twr_cfg.default_rotation.into()),
-            display_items: vec![AtcDisplayItem::Map {id: twr_cfg.video_map_id.to_string(), visible: true}]
+            display_items: vec![AtcDisplayItem::Map {id: twr_cfg.video_map_id.to_string(), visible: true}],
+            settings: Vec::new()
         }
     }
 
@@ -282,7 +389,8 @@ impl AtcDisplay {
                 center: area.visibility_center,
                 screen_height: Length::from_nautical_miles(f64::from(area.surveillance_range) * 2_f64),
                 rotation: Angle::from_radians(0_f64),
-                display_items: display_items.0
+                display_items: display_items.0,
+                settings: Vec::new()
             },
             AtcDisplay {
                 name: "STARS (Top Down Mode)".to_string(),
@@ -290,7 +398,8 @@ impl AtcDisplay {
                 center: area.visibility_center,
                 screen_height: Length::from_nautical_miles(f64::from(area.surveillance_range) * 2_f64),
                 rotation: Angle::from_radians(0_f64),
-                display_items: display_items.1
+                display_items: display_items.1,
+                settings: Vec::new()
             }
         ]
     }
@@ -306,7 +415,8 @@ impl AtcDisplay {
                 center: GeoPoint::default(),
                 screen_height: Length::from_nautical_miles(600_f64),
                 rotation: Angle::from_radians(0_f64),
-                display_items: display_items.0
+                display_items: display_items.0,
+                settings: Vec::new()
             });
             displays.push(AtcDisplay {
                 name: format!("ERAM {} (Top Down Mode)", geo_map.name),
@@ -314,7 +424,8 @@ impl AtcDisplay {
                 center: GeoPoint::default(),
                 screen_height: Length::from_nautical_miles(600_f64),
                 rotation: Angle::from_radians(0_f64),
-                display_items: display_items.1
+                display_items: display_items.1,
+                settings: Vec::new()
             });
         }
 
@@ -5,6 +5,7 @@ use serde::{Deserialize, Serialize};
 use crate::loaders::vnas_crc::CrcVideoMapRef;
 use crate::loaders::vnas_crc::facility::CrcFacility;
 use super::display::AtcDisplay;
+use super::layout::ScreenLayout;
 use super::position::AtcPosition;
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -12,7 +13,10 @@ pub struct AtcFacility {
     pub name: String,
     pub displays: Vec<AtcDisplay>,
     pub child_facilities: Vec<AtcFacility>,
-    pub positions: Vec<AtcPosition>
+    /// Missing from packages exported before EuroScope positions were added.
+    #[serde(default)]
+    pub positions: Vec<AtcPosition>,
+    pub layout: Option<ScreenLayout>
 }
 
 impl AtcFacility {
@@ -47,7 +51,8 @@ impl AtcFacility {
             name: value.name.to_string(),
             child_facilities: children,
             displays: displays,
-            positions: Vec::new()
+            positions: Vec::new(),
+            layout: None
         })
     }
 }
\ No newline at end of file
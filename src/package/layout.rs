@@ -0,0 +1,79 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::loaders::euroscope::screen::{EsListSettings, EsScreenSettings};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListColumn {
+    pub header: String,
+    pub item: String,
+    /// The width in characters.
+    pub width: u16,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListLayout {
+    /// The kind of list, e.g. `departure`, `arrival`, `sil` or `conflict`.
+    pub kind: String,
+    pub name: String,
+    /// The top left corner in screen pixels.
+    pub position: Option<(i32, i32)>,
+    pub size: Option<(u32, u32)>,
+    pub visible: bool,
+    pub sort_column: Option<String>,
+    pub sort_descending: bool,
+    pub columns: Vec<ListColumn>,
+}
+
+/// The working layout of a controller: their list windows and screen options.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScreenLayout {
+    pub options: BTreeMap<String, String>,
+    pub lists: Vec<ListLayout>,
+}
+
+impl ScreenLayout {
+    pub fn from_es_settings(screen: Option<EsScreenSettings>, lists: Option<EsListSettings>) -> Self {
+        let mut ret_val = ScreenLayout::default();
+        if let Some(screen) = screen {
+            ret_val.options.extend(screen.options);
+        }
+        if let Some(lists) = lists {
+            ret_val.lists = lists
+                .lists
+                .into_iter()
+                .map(|list| {
+                    let (sort_column, sort_descending) = match list.sort {
+                        Some((column, descending)) => (Some(column), descending),
+                        None => (None, false),
+                    };
+                    ListLayout {
+                        kind: list.kind.to_string(),
+                        name: list.name,
+                        position: list.position,
+                        size: list.size,
+                        visible: list.visible,
+                        sort_column,
+                        sort_descending,
+                        columns: list
+                            .columns
+                            .into_iter()
+                            .map(|column| ListColumn {
+                                header: column.header,
+                                item: column.item,
+                                width: column.width,
+                            })
+                            .collect(),
+                    }
+                })
+                .collect();
+        }
+        ret_val
+    }
+
+    /// The first list of `kind`, e.g. `departure`.
+    pub fn list(&self, kind: &str) -> Option<&ListLayout> {
+        self.lists.iter().find(|list| list.kind.eq_ignore_ascii_case(kind))
+    }
+}
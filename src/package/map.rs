@@ -1,12 +1,16 @@
 use crate::loaders::vnas_crc::CrcVideoMapRef;
 use crate::loaders::{
-    ese::FreeTextGroup,
+    ese::{airspace::{AirspaceSector, MsawArea}, procedure::ResolvedProcedure, FreeTextGroup},
     euroscope::{
+        ground::{GroundArea, GroundStand},
         line::{ColouredLine, LineGroup},
+        position::{Position, Valid},
         sector::{LabelGroup, RegionGroup},
+        topsky::{TopSkyColours, TopSkyMap, TopSkyMapItem},
     },
 };
 use anyhow::{anyhow, bail, Context};
+use super::coverage::CoverageSource;
 use geojson::{Feature, FeatureCollection, GeoJson, Geometry, Value};
 use serde::{Deserialize, Serialize};
 use serde_json::Map;
@@ -141,11 +145,14 @@ impl AtcMap {
 
     pub fn try_from_es_freetext_group(sector_file_id: String, item_type: String, value: FreeTextGroup) -> anyhow::Result<Self> {
         let name = format!("{}_{}_{}", sector_file_id, item_type, value.name);
+        let group_path = serde_json::to_value(value.path())?;
         let mut features = Vec::with_capacity(value.entries.capacity());
         for label in value.entries {
             // Properties
             let mut props_map = Map::new();
             props_map.insert("itemType".to_string(), serde_json::to_value(&item_type)?);
+            props_map.insert("group".to_string(), serde_json::to_value(&value.name)?);
+            props_map.insert("groupPath".to_string(), group_path.clone());
             props_map.insert("text".to_string(), serde_json::to_value(label.text.to_string())?);
             props_map.insert("showText".to_string(), serde_json::to_value(true)?);
 
@@ -170,6 +177,306 @@ impl AtcMap {
         })
     }
 
+    pub fn try_from_es_procedure(sector_file_id: String, item_type: String, value: ResolvedProcedure) -> anyhow::Result<Self> {
+        let name = format!("{}_{}_{} {} {}", sector_file_id, item_type, value.airport, value.runway, value.identifier);
+
+        // Properties
+        let mut props_map = Map::new();
+        props_map.insert("itemType".to_string(), serde_json::to_value(&item_type)?);
+        props_map.insert("text".to_string(), serde_json::to_value(value.identifier.to_string())?);
+
+        let points = value.points.iter().map(|(_, pos)| vec![pos.lon, pos.lat]).collect::<Vec<Vec<f64>>>();
+
+        Ok(AtcMap {
+            name,
+            data: AtcMapData::Embedded {
+                features: FeatureCollection {
+                    bbox: None,
+                    features: vec![Feature {
+                        id: None,
+                        bbox: None,
+                        foreign_members: None,
+                        geometry: Some(Geometry::new(Value::LineString(points))),
+                        properties: Some(props_map),
+                    }],
+                    foreign_members: None,
+                }
+            }
+        })
+    }
+
+    pub fn try_from_es_msaw_area(sector_file_id: String, item_type: String, value: MsawArea) -> anyhow::Result<Self> {
+        let name = format!("{}_{}_{}", sector_file_id, item_type, value.name);
+
+        // Properties
+        let mut props_map = Map::new();
+        props_map.insert("itemType".to_string(), serde_json::to_value(&item_type)?);
+        props_map.insert("altitude".to_string(), serde_json::to_value(value.altitude)?);
+        props_map.insert("text".to_string(), serde_json::to_value(value.name.to_string())?);
+
+        let mut points = value.vertices.iter().map(|vert| vec![vert.lon, vert.lat]).collect::<Vec<Vec<f64>>>();
+        if let Some(start_pt) = points.first() {
+            points.push(start_pt.clone());
+        }
+
+        Ok(AtcMap {
+            name,
+            data: AtcMapData::Embedded {
+                features: FeatureCollection {
+                    bbox: None,
+                    features: vec![Feature {
+                        id: None,
+                        bbox: None,
+                        foreign_members: None,
+                        geometry: Some(Geometry::new(Value::Polygon(vec![points]))),
+                        properties: Some(props_map),
+                    }],
+                    foreign_members: None,
+                }
+            }
+        })
+    }
+
+    /// A TopSky map, named after its folder and map name with the folder first, e.g. `..._topsky_EGLL\Stands`.
+    pub fn try_from_topsky_map(owner_id: String, item_type: String, value: TopSkyMap, colours: &TopSkyColours) -> anyhow::Result<Self> {
+        let name = if value.folder.is_empty() {
+            format!("{}_{}_{}", owner_id, item_type, value.name)
+        } else {
+            format!("{}_{}_{}\\{}", owner_id, item_type, value.folder, value.name)
+        };
+        let group_path = serde_json::to_value([&value.folder, &value.name])?;
+        let active = serde_json::to_value(value.active.iter().map(|active| active.to_string()).collect::<Vec<_>>())?;
+        let colour_value = |colour: &Option<String>| {
+            colour
+                .as_deref()
+                .and_then(|colour| colours.get(colour))
+                .map(|colour| serde_json::to_value(format!("#{:02X}{:02X}{:02X}", colour.r, colour.g, colour.b)))
+                .transpose()
+        };
+
+        let mut features = Vec::with_capacity(value.items.len());
+        for item in value.items {
+            // Properties
+            let mut props_map = Map::new();
+            props_map.insert("itemType".to_string(), serde_json::to_value(&item_type)?);
+            props_map.insert("group".to_string(), serde_json::to_value(&value.folder)?);
+            props_map.insert("groupPath".to_string(), group_path.clone());
+            props_map.insert("active".to_string(), active.clone());
+
+            let geometry = match item {
+                TopSkyMapItem::Line { points, colour } => {
+                    if let Some(colour) = colour_value(&colour)? {
+                        props_map.insert("color".to_string(), colour);
+                    }
+                    Value::LineString(points.iter().map(|point| vec![point.lon, point.lat]).collect())
+                }
+                TopSkyMapItem::Polygon { points, colour, fill } => {
+                    if let Some(colour) = colour_value(&colour)? {
+                        props_map.insert("color".to_string(), colour);
+                    }
+                    props_map.insert("fill".to_string(), serde_json::to_value(fill)?);
+                    let mut points = points.iter().map(|point| vec![point.lon, point.lat]).collect::<Vec<Vec<f64>>>();
+                    if let Some(start_pt) = points.first() {
+                        points.push(start_pt.clone());
+                    }
+                    Value::Polygon(vec![points])
+                }
+                TopSkyMapItem::Text { position, text, colour } => {
+                    if let Some(colour) = colour_value(&colour)? {
+                        props_map.insert("textColor".to_string(), colour);
+                    }
+                    props_map.insert("text".to_string(), serde_json::to_value(text)?);
+                    props_map.insert("showText".to_string(), serde_json::to_value(true)?);
+                    Value::Point(vec![position.lon, position.lat])
+                }
+                TopSkyMapItem::Symbol { symbol, position, label, colour } => {
+                    if let Some(colour) = colour_value(&colour)? {
+                        props_map.insert("color".to_string(), colour);
+                    }
+                    props_map.insert("symbol".to_string(), serde_json::to_value(symbol)?);
+                    if let Some(label) = label {
+                        props_map.insert("text".to_string(), serde_json::to_value(label)?);
+                        props_map.insert("showText".to_string(), serde_json::to_value(true)?);
+                    }
+                    Value::Point(vec![position.lon, position.lat])
+                }
+            };
+
+            features.push(Feature {
+                id: None,
+                bbox: None,
+                foreign_members: None,
+                geometry: Some(Geometry::new(geometry)),
+                properties: Some(props_map),
+            });
+        }
+
+        Ok(AtcMap {
+            name,
+            data: AtcMapData::Embedded {
+                features: FeatureCollection {
+                    bbox: None,
+                    features,
+                    foreign_members: None,
+                }
+            }
+        })
+    }
+
+    /// The ground map areas of one type at `airport`, drawn as polygons.
+    pub fn try_from_ground_areas(owner_id: String, item_type: String, airport: String, areas: Vec<GroundArea>) -> anyhow::Result<Self> {
+        let name = format!("{}_{}_{}", owner_id, item_type, airport);
+        let mut features = Vec::with_capacity(areas.len());
+        for area in areas {
+            // Properties
+            let mut props_map = Map::new();
+            props_map.insert("itemType".to_string(), serde_json::to_value(&item_type)?);
+            props_map.insert("text".to_string(), serde_json::to_value(area.name.to_string())?);
+
+            let mut points = area.points.iter().map(|vert| vec![vert.lon, vert.lat]).collect::<Vec<Vec<f64>>>();
+            if let Some(start_pt) = points.first() {
+                points.push(start_pt.clone());
+            }
+
+            features.push(Feature {
+                id: None,
+                bbox: None,
+                foreign_members: None,
+                geometry: Some(Geometry::new(Value::Polygon(vec![points]))),
+                properties: Some(props_map),
+            });
+        }
+
+        Ok(AtcMap {
+            name,
+            data: AtcMapData::Embedded {
+                features: FeatureCollection {
+                    bbox: None,
+                    features,
+                    foreign_members: None,
+                }
+            }
+        })
+    }
+
+    /// The outlines of the stands at `airport`. Stands without an outline or a radius are left out.
+    pub fn try_from_ground_stands(owner_id: String, item_type: String, airport: String, stands: &[GroundStand]) -> anyhow::Result<Self> {
+        let name = format!("{}_{}_{}", owner_id, item_type, airport);
+        let mut features = Vec::with_capacity(stands.len());
+        for stand in stands {
+            let mut points = stand.outline().iter().map(|vert| vec![vert.lon, vert.lat]).collect::<Vec<Vec<f64>>>();
+            if points.is_empty() {
+                continue;
+            }
+            if points.first() != points.last() {
+                points.push(points[0].clone());
+            }
+
+            // Properties
+            let mut props_map = Map::new();
+            props_map.insert("itemType".to_string(), serde_json::to_value(&item_type)?);
+            props_map.insert("text".to_string(), serde_json::to_value(stand.name.to_string())?);
+
+            features.push(Feature {
+                id: None,
+                bbox: None,
+                foreign_members: None,
+                geometry: Some(Geometry::new(Value::Polygon(vec![points]))),
+                properties: Some(props_map),
+            });
+        }
+
+        Ok(AtcMap {
+            name,
+            data: AtcMapData::Embedded {
+                features: FeatureCollection {
+                    bbox: None,
+                    features,
+                    foreign_members: None,
+                }
+            }
+        })
+    }
+
+    /// The boundary of an ESE sector, from [`crate::loaders::ese::Ese::sector_polygon`].
+    pub fn try_from_es_sector(sector_file_id: String, item_type: String, value: &AirspaceSector, polygon: Vec<Position<Valid>>) -> anyhow::Result<Self> {
+        let name = format!("{}_{}_{}", sector_file_id, item_type, value.name);
+
+        // Properties
+        let mut props_map = Map::new();
+        props_map.insert("itemType".to_string(), serde_json::to_value(&item_type)?);
+        props_map.insert("text".to_string(), serde_json::to_value(value.name.to_string())?);
+        props_map.insert("bottom".to_string(), serde_json::to_value(value.bottom)?);
+        props_map.insert("top".to_string(), serde_json::to_value(value.top)?);
+        props_map.insert("owners".to_string(), serde_json::to_value(&value.owners)?);
+
+        let mut points = polygon.iter().map(|vert| vec![vert.lon, vert.lat]).collect::<Vec<Vec<f64>>>();
+        if let Some(start_pt) = points.first() {
+            if points.last() != Some(start_pt) {
+                points.push(start_pt.clone());
+            }
+        }
+
+        Ok(AtcMap {
+            name,
+            data: AtcMapData::Embedded {
+                features: FeatureCollection {
+                    bbox: None,
+                    features: vec![Feature {
+                        id: None,
+                        bbox: None,
+                        foreign_members: None,
+                        geometry: Some(Geometry::new(Value::Polygon(vec![points]))),
+                        properties: Some(props_map),
+                    }],
+                    foreign_members: None,
+                }
+            }
+        })
+    }
+
+    /// Coverage of `source` at each of `flight_levels`, as one polygon per covered level.
+    ///
+    /// Where the cone of silence applies, the polygon has an inner ring for it.
+    pub fn try_from_coverage(owner_id: String, source: &CoverageSource, flight_levels: &[u32]) -> anyhow::Result<Self> {
+        let name = format!("{}_coverage_{}", owner_id, source.name);
+        let mut features = Vec::with_capacity(flight_levels.len());
+        for ring in flight_levels.iter().filter_map(|level| source.coverage_ring(*level)) {
+            // Properties
+            let mut props_map = Map::new();
+            props_map.insert("itemType".to_string(), serde_json::to_value("coverage")?);
+            props_map.insert("flightLevel".to_string(), serde_json::to_value(ring.flight_level)?);
+            props_map.insert("text".to_string(), serde_json::to_value(source.name.to_string())?);
+
+            let mut rings = vec![source.circle(ring.outer_radius)];
+            if ring.inner_radius.as_meters() > 0_f64 {
+                // A hole must wind the opposite way to the outer ring
+                let mut hole = source.circle(ring.inner_radius);
+                hole.reverse();
+                rings.push(hole);
+            }
+
+            features.push(Feature {
+                id: None,
+                bbox: None,
+                foreign_members: None,
+                geometry: Some(Geometry::new(Value::Polygon(rings))),
+                properties: Some(props_map),
+            });
+        }
+
+        Ok(AtcMap {
+            name,
+            data: AtcMapData::Embedded {
+                features: FeatureCollection {
+                    bbox: None,
+                    features,
+                    foreign_members: None,
+                }
+            }
+        })
+    }
+
     pub fn try_from_crc_video_map(map_ref: &CrcVideoMapRef, facility_file_path: impl AsRef<Path>, facility_name: String) -> anyhow::Result<AtcMap> {
         // Determine path
         let video_map_path = facility_file_path
@@ -7,6 +7,9 @@ use display::{AtcDisplay, AtcDisplayBackground, AtcDisplayType, DisplayDefaultCo
 use flate2::{read::GzDecoder, write::GzEncoder, Compression, GzBuilder};
 use geojson::{Feature, FeatureCollection, GeoJson, Geometry, Value};
 use map::{AtcMap, AtcMapData};
+use position::AtcPosition;
+use layout::ScreenLayout;
+use reference::ReferenceData;
 use serde::{Deserialize, Serialize};
 use serde_json::Map;
 use tar::{Archive, Header};
@@ -16,6 +19,7 @@ use symbol::{AtcMapSymbol, SymbolDrawItem, SymbolIcon};
 
 use crate::loaders::euroscope::{
     colour::Colour,
+    ground::{GroundArea, GroundAreaType, GroundStand},
     line::{ColouredLine, LineGroup},
     loader::EuroScopeResult,
     sector::{LabelGroup, RegionGroup},
@@ -24,13 +28,18 @@ use crate::loaders::euroscope::{
 };
 
 mod facility;
+use crate::loaders::ese::{procedure::ProcedureResolver, ProcedureType};
 use crate::loaders::vnas_crc::{CrcPackage, CrcVideoMapRef};
 use crate::package::display::AtcDisplayItem;
 pub use facility::AtcFacility;
 
+pub mod coverage;
 pub mod display;
+pub mod layout;
 pub mod map;
 pub mod position;
+pub mod reference;
+pub mod squawk;
 pub mod symbol;
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -39,6 +48,11 @@ pub struct AtcScopePackage {
     pub maps: HashMap<String, AtcMap>,
     pub symbols: HashMap<String, AtcMapSymbol>,
     pub display_types: HashMap<String, AtcDisplayType>,
+    pub reference_data: Option<ReferenceData>,
+    /// Problems that left something out of the package without stopping the conversion, such as procedures
+    /// that could not be drawn. Not exported.
+    #[serde(skip)]
+    pub non_critical_errors: Vec<String>,
 }
 
 impl TryFrom<EuroScopeResult> for AtcScopePackage {
@@ -49,9 +63,33 @@ impl TryFrom<EuroScopeResult> for AtcScopePackage {
         let mut symbols = HashMap::new();
         let mut display_types = HashMap::new();
         let mut facilities = Vec::new();
+        let mut sector_positions = HashMap::new();
+        let mut non_critical_errors = Vec::new();
 
         // Parse "maps"
         for sector in value.sectors {
+            // ESE Procedures
+            if let Some(ese_file) = &sector.1 .1 {
+                let resolution = ProcedureResolver::new(&sector.1 .0, ese_file).resolve();
+                non_critical_errors.extend(resolution.unresolved.iter().map(|unresolved| format!("{}: {}", sector.0, unresolved)));
+                for entry in resolution.procedures {
+                    if entry.points.len() < 2 {
+                        non_critical_errors.push(format!(
+                            "{}: {} ({} {}) has fewer than 2 known points and was not drawn",
+                            sector.0, entry.identifier, entry.airport, entry.runway
+                        ));
+                        continue;
+                    }
+                    let item_type = match entry.proc_type {
+                        ProcedureType::SID => SymbologyItemType::Sids,
+                        ProcedureType::STAR => SymbologyItemType::Stars,
+                    };
+                    let val = AtcMap::try_from_es_procedure(sector.0.to_string(), item_type.to_key_string(), entry)?;
+
+                    maps.insert(val.name.to_string(), val);
+                }
+            }
+
             // Geo
             for geo in sector.1 .0.geo_entries {
                 let val = AtcMap::try_from_es_line_group(sector.0.to_string(), SymbologyItemType::Geo.to_key_string(), geo)?;
@@ -124,11 +162,38 @@ impl TryFrom<EuroScopeResult> for AtcScopePackage {
 
             // ESE
             if let Some(ese_file) = sector.1 .1 {
+                for entry in &ese_file.sectors {
+                    let polygon = ese_file.sector_polygon(entry);
+                    if polygon.len() < 3 {
+                        continue;
+                    }
+                    let val = AtcMap::try_from_es_sector(sector.0.to_string(), SymbologyItemType::Sector.to_key_string(), entry, polygon)?;
+
+                    maps.insert(val.name.to_string(), val);
+                }
+
                 for entry in ese_file.free_text {
                     let val = AtcMap::try_from_es_freetext_group(sector.0.to_string(), SymbologyItemType::Label.to_key_string(), entry)?;
 
                     maps.insert(val.name.to_string(), val);
                 }
+
+                for entry in ese_file.msaw_areas {
+                    let val = AtcMap::try_from_es_msaw_area(sector.0.to_string(), "msaw".to_string(), entry)?;
+
+                    maps.insert(val.name.to_string(), val);
+                }
+
+                sector_positions.insert(
+                    sector.0.to_string(),
+                    ese_file.atc_positions.iter().map(AtcPosition::from_es_position).collect::<Vec<_>>(),
+                );
+
+                for entry in ese_file.radar_sites {
+                    let val = AtcMapSymbol::try_from_es_position(sector.0.to_string(), "radars".to_string(), entry.name, entry.position)?;
+
+                    symbols.insert(val.name.to_string(), val);
+                }
             }
 
             // Airports
@@ -176,15 +241,65 @@ impl TryFrom<EuroScopeResult> for AtcScopePackage {
             }
         }
 
+        let mut reference_data: Option<ReferenceData> = None;
+        let mut has_ground_maps = false;
         for prf in value.profiles {
+            // Reference data is shared by all profiles, the first profile's entries win
+            if let Some(icao_data) = &prf.icao_data {
+                reference_data.get_or_insert_with(ReferenceData::default).merge_es_icao(icao_data);
+            }
+
             let mut facility = AtcFacility::default();
             facility.name = prf.prf_name;
+            facility.positions = sector_positions.get(&prf.default_sector_id).cloned().unwrap_or_default();
+            if prf.screen_settings.is_some() || prf.list_settings.is_some() {
+                facility.layout = Some(ScreenLayout::from_es_settings(prf.screen_settings, prf.list_settings));
+            }
 
-            // Parse symbology
-            display_types.insert(
-                prf.prf_file.to_string(),
-                AtcDisplayType::try_from_es_symbology(prf.prf_file.to_string(), prf.symbology)?,
-            );
+            // TopSky maps
+            if let Some(topsky_maps) = prf.topsky_maps {
+                for entry in topsky_maps.maps {
+                    let val = AtcMap::try_from_topsky_map(prf.prf_file.to_string(), "topsky".to_string(), entry, &topsky_maps.colours)?;
+
+                    maps.insert(val.name.to_string(), val);
+                }
+            }
+
+            // Ground radar plugin stands and maps
+            if let Some(ground_maps) = prf.ground_maps {
+                let mut area_groups: HashMap<(String, GroundAreaType), Vec<GroundArea>> = HashMap::new();
+                for area in ground_maps.areas {
+                    area_groups.entry((area.airport.to_uppercase(), area.area_type)).or_default().push(area);
+                }
+                for ((airport, area_type), areas) in area_groups {
+                    let val = AtcMap::try_from_ground_areas(prf.prf_file.to_string(), area_type.to_key_string(), airport, areas)?;
+
+                    maps.insert(val.name.to_string(), val);
+                }
+
+                let mut stand_groups: HashMap<String, Vec<GroundStand>> = HashMap::new();
+                for stand in ground_maps.stands {
+                    stand_groups.entry(stand.airport.to_uppercase()).or_default().push(stand);
+                }
+                for (airport, stands) in stand_groups {
+                    for stand in &stands {
+                        let val = AtcMapSymbol::try_from_ground_stand(prf.prf_file.to_string(), "stand".to_string(), stand)?;
+                        symbols.insert(val.name.to_string(), val);
+                    }
+
+                    let val = AtcMap::try_from_ground_stands(prf.prf_file.to_string(), "stand".to_string(), airport, &stands)?;
+                    maps.insert(val.name.to_string(), val);
+                }
+
+                has_ground_maps = true;
+            }
+
+            // Parse symbology and tags
+            let mut display_type = AtcDisplayType::try_from_es_symbology(prf.prf_file.to_string(), prf.symbology)?;
+            if let Some(tags) = prf.tags {
+                display_type.datablock.add_es_tags(tags);
+            }
+            display_types.insert(prf.prf_file.to_string(), display_type);
 
             // Parse ASRs
             for asr in prf.asrs {
@@ -195,11 +310,25 @@ impl TryFrom<EuroScopeResult> for AtcScopePackage {
             facilities.push(facility);
         }
 
+        // Ground radar plugin maps are drawn on ground displays
+        if has_ground_maps {
+            display_types.insert(
+                "ground-day".to_string(),
+                AtcScopePackage::get_es_ground_display_type("ground-day", "#005C73", &AtcScopePackage::GROUND_DAY_COLOURS),
+            );
+            display_types.insert(
+                "ground-night".to_string(),
+                AtcScopePackage::get_es_ground_display_type("ground-night", "#393939", &AtcScopePackage::GROUND_NIGHT_COLOURS),
+            );
+        }
+
         Ok(AtcScopePackage {
             facilities: facilities,
             symbols: symbols,
             maps: maps,
             display_types,
+            reference_data,
+            non_critical_errors,
         })
     }
 }
@@ -233,6 +362,7 @@ impl TryFrom<&CrcPackage> for AtcScopePackage {
                 symbol_icons: Self::get_eram_symbols(),
                 line_types: Self::get_eram_lines(),
                 background: AtcDisplayBackground::Color("#000000".to_string()),
+                datablock: Default::default(),
             },
         );
 
@@ -245,89 +375,18 @@ impl TryFrom<&CrcPackage> for AtcScopePackage {
                 symbol_icons: Default::default(),
                 line_types: Default::default(),
                 background: AtcDisplayBackground::Color("#000000".to_string()),
+                datablock: Default::default(),
             },
         );
 
         package.display_types.insert(
             "asdex-day".to_string(),
-            AtcDisplayType {
-                id: "asdex-day".to_string(),
-                map_defaults: HashMap::from([
-                    (
-                        "taxiway".to_string(),
-                        DisplayDefaultConfig {
-                            color: Colour { r: 45, g: 45, b: 45 },
-                            ..Default::default()
-                        },
-                    ),
-                    (
-                        "apron".to_string(),
-                        DisplayDefaultConfig {
-                            color: Colour { r: 70, g: 70, b: 70 },
-                            ..Default::default()
-                        },
-                    ),
-                    (
-                        "structure".to_string(),
-                        DisplayDefaultConfig {
-                            color: Colour { r: 96, g: 96, b: 96 },
-                            ..Default::default()
-                        },
-                    ),
-                    (
-                        "runway".to_string(),
-                        DisplayDefaultConfig {
-                            color: Colour { r: 0, g: 0, b: 0 },
-                            ..Default::default()
-                        },
-                    ),
-                ]),
-                symbol_defaults: Default::default(),
-                symbol_icons: Default::default(),
-                line_types: Default::default(),
-                background: AtcDisplayBackground::Color("#005C73".to_string()),
-            },
+            Self::get_ground_display_type("asdex-day", "#005C73", &Self::ASDEX_DAY_COLOURS),
         );
 
         package.display_types.insert(
             "asdex-night".to_string(),
-            AtcDisplayType {
-                id: "asdex-night".to_string(),
-                map_defaults: HashMap::from([
-                    (
-                        "taxiway".to_string(),
-                        DisplayDefaultConfig {
-                            color: Colour { r: 16, g: 37, b: 76 },
-                            ..Default::default()
-                        },
-                    ),
-                    (
-                        "apron".to_string(),
-                        DisplayDefaultConfig {
-                            color: Colour { r: 17, g: 52, b: 93 },
-                            ..Default::default()
-                        },
-                    ),
-                    (
-                        "structure".to_string(),
-                        DisplayDefaultConfig {
-                            color: Colour { r: 32, g: 60, b: 98 },
-                            ..Default::default()
-                        },
-                    ),
-                    (
-                        "runway".to_string(),
-                        DisplayDefaultConfig {
-                            color: Colour { r: 0, g: 0, b: 0 },
-                            ..Default::default()
-                        },
-                    ),
-                ]),
-                symbol_defaults: Default::default(),
-                symbol_icons: Default::default(),
-                line_types: Default::default(),
-                background: AtcDisplayBackground::Color("#393939".to_string()),
-            },
+            Self::get_ground_display_type("asdex-night", "#393939", &Self::ASDEX_NIGHT_COLOURS),
         );
 
         package.display_types.insert(
@@ -339,6 +398,7 @@ impl TryFrom<&CrcPackage> for AtcScopePackage {
                 symbol_icons: Default::default(),
                 line_types: Default::default(),
                 background: display::AtcDisplayBackground::Satellite,
+                datablock: Default::default(),
             },
         );
 
@@ -347,6 +407,77 @@ impl TryFrom<&CrcPackage> for AtcScopePackage {
 }
 
 impl AtcScopePackage {
+    /// Map colours of the ASDE-X day display by ground map item type.
+    const ASDEX_DAY_COLOURS: [(&'static str, Colour); 4] = [
+        ("taxiway", Colour::new(45, 45, 45)),
+        ("apron", Colour::new(70, 70, 70)),
+        ("structure", Colour::new(96, 96, 96)),
+        ("runway", Colour::new(0, 0, 0)),
+    ];
+
+    /// Map colours of the ASDE-X night display by ground map item type.
+    const ASDEX_NIGHT_COLOURS: [(&'static str, Colour); 4] = [
+        ("taxiway", Colour::new(16, 37, 76)),
+        ("apron", Colour::new(17, 52, 93)),
+        ("structure", Colour::new(32, 60, 98)),
+        ("runway", Colour::new(0, 0, 0)),
+    ];
+
+    /// Map colours of the day display for ground radar plugin maps.
+    const GROUND_DAY_COLOURS: [(&'static str, Colour); 6] = [
+        ("taxiway", Colour::new(45, 45, 45)),
+        ("apron", Colour::new(70, 70, 70)),
+        ("structure", Colour::new(96, 96, 96)),
+        ("runway", Colour::new(0, 0, 0)),
+        ("grass", Colour::new(40, 72, 48)),
+        ("stand", Colour::new(85, 85, 85)),
+    ];
+
+    /// Map colours of the night display for ground radar plugin maps.
+    const GROUND_NIGHT_COLOURS: [(&'static str, Colour); 6] = [
+        ("taxiway", Colour::new(16, 37, 76)),
+        ("apron", Colour::new(17, 52, 93)),
+        ("structure", Colour::new(32, 60, 98)),
+        ("runway", Colour::new(0, 0, 0)),
+        ("grass", Colour::new(12, 40, 52)),
+        ("stand", Colour::new(24, 66, 110)),
+    ];
+
+    /// A ground display type that draws each map item type in `colours` in that colour.
+    fn get_ground_display_type(id: &str, background: &str, colours: &[(&str, Colour)]) -> AtcDisplayType {
+        AtcDisplayType {
+            id: id.to_string(),
+            map_defaults: colours
+                .iter()
+                .map(|(item_type, colour)| {
+                    (
+                        item_type.to_string(),
+                        DisplayDefaultConfig {
+                            color: *colour,
+                            ..Default::default()
+                        },
+                    )
+                })
+                .collect(),
+            symbol_defaults: Default::default(),
+            symbol_icons: Default::default(),
+            line_types: Default::default(),
+            background: AtcDisplayBackground::Color(background.to_string()),
+            datablock: Default::default(),
+        }
+    }
+
+    /// A ground display type for ground radar plugin maps, which also draws the symbols of each item type in its map colour.
+    fn get_es_ground_display_type(id: &str, background: &str, colours: &[(&str, Colour)]) -> AtcDisplayType {
+        let mut display_type = Self::get_ground_display_type(id, background, colours);
+        display_type.symbol_defaults = display_type
+            .map_defaults
+            .iter()
+            .map(|(item_type, config)| (item_type.clone(), (config.clone(), config.clone())))
+            .collect();
+        display_type
+    }
+
     fn get_eram_symbols() -> HashMap<String, SymbolIcon> {
         HashMap::from([
             (
@@ -760,7 +891,9 @@ impl AtcScopePackage {
             facilities: self.facilities.clone(),
             maps: new_maps,
             symbols: self.symbols.clone(),
-            display_types: self.display_types.clone()
+            display_types: self.display_types.clone(),
+            reference_data: self.reference_data.clone(),
+            non_critical_errors: Vec::new()
         };
 
         // Save Package json
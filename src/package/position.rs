@@ -1,5 +1,7 @@
+use aviation_calc_util::geo::GeoPoint;
 use serde::{Deserialize, Serialize};
 
+use crate::loaders::ese;
 
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -9,7 +11,37 @@ pub struct AtcPosition {
     pub callsign: Option<String>,
     pub frequency: Option<(u16, u16)>,
     pub tranceivers: Vec<String>,
-    pub display_configs: Vec<PositionDisplayConfig>
+    pub display_configs: Vec<PositionDisplayConfig>,
+    pub identifier: Option<String>,
+    pub squawk_range: Option<(u16, u16)>,
+    pub visibility_centers: Vec<GeoPoint>
+}
+
+impl AtcPosition {
+    pub fn from_es_position(value: &ese::AtcPosition) -> Self {
+        AtcPosition {
+            name: value.name.to_string(),
+            radio_name: Some(value.rt_callsign.to_string()),
+            callsign: Some(value.full_identifier.to_string()),
+            frequency: Self::parse_es_frequency(&value.radio_freq),
+            identifier: Some(value.short_identifier.to_string()),
+            squawk_range: value.start_squawk.zip(value.end_squawk),
+            visibility_centers: value
+                .vis_centres
+                .iter()
+                .flatten()
+                .map(|centre| GeoPoint::from_degs_and_ft(centre.lat, centre.lon, 0_f64))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Splits a frequency such as `118.050` into MHz and kHz parts, i.e. `(118, 50)`.
+    fn parse_es_frequency(value: &str) -> Option<(u16, u16)> {
+        let (mhz, khz) = value.trim().split_once('.')?;
+        let khz = format!("{:0<3}", khz);
+        Some((mhz.parse().ok()?, khz.get(..3)?.parse().ok()?))
+    }
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
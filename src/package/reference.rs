@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::loaders::euroscope::icao::IcaoData;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AircraftTypeInfo {
+    pub designator: String,
+    pub wake_category: Option<String>,
+    pub description: String,
+    pub engine_count: Option<u8>,
+    pub engine_type: Option<String>,
+    pub manufacturer: String,
+    pub model: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AirlineInfo {
+    pub designator: String,
+    pub name: String,
+    pub telephony: String,
+    pub country: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AirportInfo {
+    pub identifier: String,
+    pub name: String,
+    pub country: String,
+}
+
+/// Aircraft types, airlines and airports, keyed by their upper case ICAO designators.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReferenceData {
+    pub aircraft: HashMap<String, AircraftTypeInfo>,
+    pub airlines: HashMap<String, AirlineInfo>,
+    pub airports: HashMap<String, AirportInfo>,
+}
+
+impl ReferenceData {
+    pub fn from_es_icao(value: &IcaoData) -> Self {
+        let mut ret_val = Self::default();
+        ret_val.merge_es_icao(value);
+        ret_val
+    }
+
+    /// Adds the entries of `value` that are not already present.
+    pub fn merge_es_icao(&mut self, value: &IcaoData) {
+        for aircraft in &value.aircraft {
+            self.aircraft.entry(aircraft.designator.to_uppercase()).or_insert_with(|| AircraftTypeInfo {
+                designator: aircraft.designator.to_string(),
+                wake_category: aircraft.wake_category.map(String::from),
+                description: aircraft.description.to_string(),
+                engine_count: aircraft.engine_count,
+                engine_type: aircraft.engine_type.map(String::from),
+                manufacturer: aircraft.manufacturer.to_string(),
+                model: aircraft.model.to_string(),
+            });
+        }
+        for airline in &value.airlines {
+            self.airlines.entry(airline.designator.to_uppercase()).or_insert_with(|| AirlineInfo {
+                designator: airline.designator.to_string(),
+                name: airline.name.to_string(),
+                telephony: airline.telephony.to_string(),
+                country: airline.country.to_string(),
+            });
+        }
+        for airport in &value.airports {
+            self.airports.entry(airport.identifier.to_uppercase()).or_insert_with(|| AirportInfo {
+                identifier: airport.identifier.to_string(),
+                name: airport.name.to_string(),
+                country: airport.country.to_string(),
+            });
+        }
+    }
+
+    pub fn aircraft_type(&self, designator: &str) -> Option<&AircraftTypeInfo> {
+        self.aircraft.get(&designator.to_uppercase())
+    }
+
+    pub fn airport(&self, identifier: &str) -> Option<&AirportInfo> {
+        self.airports.get(&identifier.to_uppercase())
+    }
+
+    /// The airline of a callsign such as `BAW123`, taken from its first three letters.
+    pub fn airline_for_callsign(&self, callsign: &str) -> Option<&AirlineInfo> {
+        let designator = callsign.get(..3)?;
+        if !designator.chars().all(|c| c.is_ascii_alphabetic()) {
+            return None;
+        }
+        self.airlines.get(&designator.to_uppercase())
+    }
+
+    /// The spoken callsign, e.g. `SPEEDBIRD 123` for `BAW123`.
+    pub fn telephony(&self, callsign: &str) -> Option<String> {
+        let airline = self.airline_for_callsign(callsign)?;
+        if airline.telephony.is_empty() {
+            return None;
+        }
+        Some(format!("{} {}", airline.telephony, &callsign[3..]).trim_end().to_string())
+    }
+}
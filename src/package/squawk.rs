@@ -0,0 +1,238 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+};
+
+use crate::loaders::{
+    ese,
+    vnas_crc::facility::{BeaconCodeBank, CrcFacility},
+};
+
+use super::AtcFacility;
+
+/// Codes that are never handed out: emergency, radio failure and unlawful interference,
+/// the conspicuity codes and the "no code assigned" codes.
+pub const RESERVED_SQUAWKS: [u16; 8] = [0, 1000, 1200, 2000, 7000, 7500, 7600, 7700];
+
+/// A range of squawk codes available to a position, written as the four octal digits of the code (e.g. `4701`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SquawkRange {
+    pub start: u16,
+    pub end: u16,
+    pub category: Option<String>,
+    /// Lower values are used first.
+    pub priority: u32,
+    /// The STARS subset of the positions that use the range. `None` for ranges any position can use.
+    pub subset: Option<u32>,
+}
+impl SquawkRange {
+    pub fn new(start: u16, end: u16) -> Self {
+        SquawkRange {
+            start,
+            end,
+            category: None,
+            priority: 0,
+            subset: None,
+        }
+    }
+
+    pub fn from_crc_bank(bank: &BeaconCodeBank) -> Result<Self, SquawkError> {
+        let start = u16::try_from(bank.start).map_err(|_| SquawkError::InvalidRange)?;
+        let end = u16::try_from(bank.end).map_err(|_| SquawkError::InvalidRange)?;
+        Ok(SquawkRange {
+            start,
+            end,
+            category: bank.category.clone(),
+            priority: bank.priority.as_deref().map_or(u32::MAX, Self::parse_crc_priority),
+            subset: bank.subset,
+        })
+    }
+
+    fn parse_crc_priority(value: &str) -> u32 {
+        if let Ok(priority) = value.parse() {
+            return priority;
+        }
+        match value.to_lowercase().as_str() {
+            "primary" => 1,
+            "secondary" => 2,
+            "tertiary" => 3,
+            _ => u32::MAX,
+        }
+    }
+
+    pub fn overlaps(&self, other: &SquawkRange) -> bool {
+        self.start <= other.end && other.start <= self.end
+    }
+
+    /// The valid codes of this range, in order.
+    pub fn codes(&self) -> impl Iterator<Item = u16> {
+        (self.start..=self.end).filter(|code| is_valid_squawk(*code))
+    }
+
+    fn is_valid(&self) -> bool {
+        self.start <= self.end && is_valid_squawk(self.start) && is_valid_squawk(self.end)
+    }
+}
+
+/// Whether `code` is a four digit code made up only of the octal digits 0-7.
+pub fn is_valid_squawk(code: u16) -> bool {
+    code <= 7777 && [code / 1000, code / 100 % 10, code / 10 % 10, code % 10].iter().all(|digit| *digit < 8)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SquawkError {
+    UnknownPosition,
+    InvalidRange,
+    Exhausted,
+}
+
+impl Display for SquawkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::UnknownPosition => "No squawk ranges are defined for the position",
+                Self::InvalidRange => "Invalid squawk range",
+                Self::Exhausted => "All squawk codes for the position are in use",
+            }
+        )
+    }
+}
+
+impl std::error::Error for SquawkError {}
+
+/// Two squawk ranges that share at least one code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SquawkOverlap<'a> {
+    pub first_owner: &'a str,
+    pub first: &'a SquawkRange,
+    pub second_owner: &'a str,
+    pub second: &'a SquawkRange,
+}
+
+/// Hands out squawk codes from the ranges assigned to each position or facility.
+#[derive(Debug, Clone, Default)]
+pub struct SquawkAllocator {
+    ranges: HashMap<String, Vec<SquawkRange>>,
+}
+
+impl SquawkAllocator {
+    /// Ranges from the `start_squawk` / `end_squawk` of ESE positions, keyed by position ID.
+    pub fn from_es_positions(positions: &[ese::AtcPosition]) -> Self {
+        let mut allocator = Self::default();
+        for position in positions {
+            if let (Some(start), Some(end)) = (position.start_squawk, position.end_squawk) {
+                // Invalid ranges are left out, the same as positions without a range
+                let _ = allocator.add_range(position.short_identifier.to_string(), SquawkRange::new(start, end));
+            }
+        }
+        allocator
+    }
+
+    /// Ranges from the squawk ranges of package positions, keyed by position identifier.
+    pub fn from_facility(facility: &AtcFacility) -> Self {
+        let mut allocator = Self::default();
+        allocator.add_facility(facility);
+        allocator
+    }
+
+    fn add_facility(&mut self, facility: &AtcFacility) {
+        for position in &facility.positions {
+            if let (Some(identifier), Some((start, end))) = (&position.identifier, position.squawk_range) {
+                let _ = self.add_range(identifier.to_string(), SquawkRange::new(start, end));
+            }
+        }
+        for child in &facility.child_facilities {
+            self.add_facility(child);
+        }
+    }
+
+    /// Ranges from the ERAM and STARS beacon code banks of a CRC facility and its children, keyed by facility ID.
+    pub fn add_crc_facility(&mut self, facility: &CrcFacility) -> Result<(), SquawkError> {
+        let eram_banks = facility.eram_configuration.iter().flat_map(|cfg| &cfg.beacon_code_banks);
+        let stars_banks = facility.stars_configuration.iter().flat_map(|cfg| &cfg.beacon_code_banks);
+        for bank in eram_banks.chain(stars_banks) {
+            self.add_range(facility.id.to_string(), SquawkRange::from_crc_bank(bank)?)?;
+        }
+        for child in &facility.child_facilities {
+            self.add_crc_facility(child)?;
+        }
+        Ok(())
+    }
+
+    pub fn add_range(&mut self, owner_id: String, range: SquawkRange) -> Result<(), SquawkError> {
+        if !range.is_valid() {
+            return Err(SquawkError::InvalidRange);
+        }
+        let ranges = self.ranges.entry(owner_id).or_default();
+        ranges.push(range);
+        ranges.sort_by_key(|range| range.priority);
+        Ok(())
+    }
+
+    pub fn ranges(&self, owner_id: &str) -> Option<&Vec<SquawkRange>> {
+        self.ranges.get(owner_id)
+    }
+
+    /// The next free code for `owner_id`, skipping reserved codes and those in `in_use`.
+    ///
+    /// Ranges are tried in priority order. If `category` is given, only ranges of that category are used.
+    pub fn allocate(&self, owner_id: &str, in_use: &HashSet<u16>, category: Option<&str>) -> Result<u16, SquawkError> {
+        self.available_codes(owner_id, in_use, category, None)?.next().ok_or(SquawkError::Exhausted)
+    }
+
+    /// Like [`SquawkAllocator::allocate`], for a STARS position of `subset`. Only ranges of that subset and
+    /// ranges without a subset are used.
+    pub fn allocate_for_subset(&self, owner_id: &str, in_use: &HashSet<u16>, category: Option<&str>, subset: u32) -> Result<u16, SquawkError> {
+        self.available_codes(owner_id, in_use, category, Some(subset))?.next().ok_or(SquawkError::Exhausted)
+    }
+
+    /// How many codes are still free for `owner_id`.
+    pub fn free_count(&self, owner_id: &str, in_use: &HashSet<u16>, category: Option<&str>) -> Result<usize, SquawkError> {
+        Ok(self.available_codes(owner_id, in_use, category, None)?.count())
+    }
+
+    fn available_codes<'a>(
+        &'a self,
+        owner_id: &str,
+        in_use: &'a HashSet<u16>,
+        category: Option<&'a str>,
+        subset: Option<u32>,
+    ) -> Result<impl Iterator<Item = u16> + 'a, SquawkError> {
+        let ranges = self.ranges.get(owner_id).ok_or(SquawkError::UnknownPosition)?;
+        Ok(ranges
+            .iter()
+            .filter(move |range| {
+                category.is_none_or(|category| range.category.as_deref().is_some_and(|cat| cat.eq_ignore_ascii_case(category)))
+            })
+            .filter(move |range| subset.is_none_or(|subset| range.subset.is_none_or(|range_subset| range_subset == subset)))
+            .flat_map(SquawkRange::codes)
+            .filter(|code| !RESERVED_SQUAWKS.contains(code) && !in_use.contains(code)))
+    }
+
+    /// All pairs of ranges, of the same or different owners, that share codes.
+    pub fn overlaps(&self) -> Vec<SquawkOverlap<'_>> {
+        let mut all_ranges = self
+            .ranges
+            .iter()
+            .flat_map(|(owner, ranges)| ranges.iter().map(move |range| (owner.as_str(), range)))
+            .collect::<Vec<_>>();
+        all_ranges.sort_by_key(|(owner, range)| (*owner, range.start, range.end));
+
+        let mut overlaps = Vec::new();
+        for (i, (first_owner, first)) in all_ranges.iter().enumerate() {
+            for (second_owner, second) in &all_ranges[i + 1..] {
+                if first.overlaps(second) {
+                    overlaps.push(SquawkOverlap {
+                        first_owner,
+                        first,
+                        second_owner,
+                        second,
+                    });
+                }
+            }
+        }
+        overlaps
+    }
+}
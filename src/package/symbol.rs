@@ -5,6 +5,7 @@ use serde::{Deserialize, Serialize};
 use serde_json::Map;
 
 use crate::loaders::euroscope::{
+    ground::GroundStand,
     position::{Position, Valid},
     symbology::SymbologyItemType,
 };
@@ -35,6 +36,21 @@ impl AtcMapSymbol {
             },
         })
     }
+
+    /// The label of a stand, with the aircraft it is for as properties.
+    pub fn try_from_ground_stand(owner_id: String, item_type: String, stand: &GroundStand) -> anyhow::Result<Self> {
+        let mut ret_val = Self::try_from_es_position(owner_id.to_string(), item_type.to_string(), stand.name.to_string(), stand.position)?;
+        // Named like the stand map of the airport, `<owner>_<item type>_<airport>`, followed by the stand
+        ret_val.name = format!("{}_{}_{}_{}", owner_id, item_type, stand.airport, stand.name);
+        if let Some(props_map) = ret_val.feature.properties.as_mut() {
+            for (key, value) in [("wtc", &stand.wake_categories), ("use", &stand.usage), ("code", &stand.codes)] {
+                if let Some(value) = value {
+                    props_map.insert(key.to_string(), serde_json::to_value(value)?);
+                }
+            }
+        }
+        Ok(ret_val)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -1,9 +1,26 @@
-use std::{fs::File, io::BufWriter, path::Path};
+use std::{collections::{HashMap, HashSet}, fs::File, io::{self, BufRead, BufWriter, Read, Write}, path::{Path, PathBuf}, sync::Arc};
 
 use directories::UserDirs;
 
-use crate::{loaders::{euroscope::loader::EuroScopeLoader, vnas_crc::CrcPackage}, package::AtcScopePackage};
-use crate::loaders::euroscope::loader::EuroScopeLoaderPrf;
+use crate::{loaders::{euroscope::loader::EuroScopeLoader, vnas_crc::CrcPackage}, package::{self, AtcScopePackage}};
+use crate::loaders::euroscope::loader::{EsPathResolver, EuroScopeLoaderPrf};
+use crate::loaders::euroscope::prf::{PrfSettings, PrfSettingsFile};
+use crate::loaders::euroscope::alias::{AliasExpander, AliasFile};
+use crate::loaders::euroscope::navdata::NavData;
+use crate::loaders::euroscope::topsky::{TopSkyActive, TopSkyMapItem};
+use crate::loaders::euroscope::ground::GroundAreaType;
+use crate::loaders::euroscope::screen::EsListKind;
+use crate::loaders::euroscope::filesystem::{FileSystem, MemoryFileSystem, TarFileSystem};
+use crate::loaders::ese::route::{RouteExpander, UnexpandedReason};
+use crate::loaders::ese::{linked::LinkedAirports, procedure::ProcedureResolver, reader::EseReader, ProcedureType};
+use crate::loaders::euroscope::reader::SctReader;
+use crate::loaders::euroscope::position::Position;
+use crate::loaders::euroscope::colour::Colour;
+use crate::loaders::euroscope::{symbology::{SymbologyInfo, SymbologyItemType}, DisplayItem, EsAsr};
+use crate::package::map::{AtcMap, AtcMapData};
+use crate::package::squawk::{SquawkAllocator, SquawkError, SquawkRange};
+use crate::loaders::vnas_crc::facility::BeaconCodeBank;
+use crate::package::{coverage::CoverageSource, display::{AtcDisplay, AtcDisplayItem, AtcDisplaySetting}};
 
 #[test]
 #[ignore]
@@ -31,9 +48,7 @@ fn test_convert_es_path_2(){
 #[ignore]
 fn test_load_es_1(){
     let prf_path = r#"C:\Users\prith\Documents\EuroScope\UK\Belfast\Belfast Combined.prf"#;
-    let mut es = EuroScopeLoader {
-        prfs: vec![EuroScopeLoaderPrf::try_new_from_prf(prf_path).unwrap()]
-    };
+    let mut es = EuroScopeLoader::new(vec![EuroScopeLoaderPrf::try_new_from_prf(prf_path).unwrap()]);
     let result = es.try_read().unwrap();
 
     let package = AtcScopePackage::try_from(result).unwrap();
@@ -70,4 +85,1108 @@ fn test_load_crc_1() {
     serde_json::to_writer(BufWriter::new(File::create(Path::new("target").join("test_crc_out_zla.json")).unwrap()), &package);
 
     //println!("{:#?}", package);
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_ese_copx_route_lookup() {
+    let ese = r#"[AIRSPACE]
+COPX:EGLL:27R:*:BPK:*:*:*:LTC_N:LON_N:*:*:BPK
+FIR_COPX:*:*:*:REDFA:*:*:*:LON_N:EISN:25000:*:REDFA
+COPX:*:*:*:LAM:*:EGLL:27L:LON_S:LTC_S:*:15000:LAM
+COPX:*:*:*:LAM:*:*:*:LON_S:LTC_S:*:15000
+"#;
+    let ese = EseReader::new(ese.as_bytes()).try_read().unwrap();
+    assert_eq!(ese.coordination_points.len(), 3);
+    assert_eq!(ese.non_critical_errors.len(), 1);
+
+    let points = ese.coordination_points_for_route(&["LTC_N", "LON_N", "EISN"]);
+    assert_eq!(points.iter().map(|point| point.fix.as_str()).collect::<Vec<_>>(), vec!["BPK", "REDFA"]);
+    assert_eq!(points[1].climb_level, Some(25000));
+    assert!(points[0].applies_to("EGLL", Some(&"27R".parse().unwrap()), "EGPH", None));
+    assert!(!points[0].applies_to("EGKK", None, "EGPH", None));
+
+    let arrival = ese.coordination_points_between("LON_S", "LTC_S")[0];
+    assert_eq!(arrival.arrival_airport.as_deref(), Some("EGLL"));
+    assert_eq!(arrival.descent_level, Some(15000));
+    assert!(arrival.applies_to("EGPH", None, "EGLL", Some(&"27L".parse().unwrap())));
+    assert!(!arrival.applies_to("EGPH", None, "EGLL", Some(&"09R".parse().unwrap())));
+    assert!(!arrival.applies_to("EGPH", None, "EGKK", None));
+}
+
+#[test]
+fn test_ese_msaw_query() {
+    let ese = r#"[AIRSPACE]
+MSAW:SNOWDONIA:5000
+COORD:N053.00.00.000:W004.30.00.000
+COORD:N053.00.00.000:W003.30.00.000
+COORD:N052.30.00.000:W003.30.00.000
+COORD:N052.30.00.000:W004.30.00.000
+MSAW:BROKEN:high
+COORD:N050.00.00.000:W001.00.00.000
+"#;
+    let ese = EseReader::new(ese.as_bytes()).try_read().unwrap();
+    assert_eq!(ese.msaw_areas.len(), 1);
+    assert_eq!(ese.msaw_areas[0].vertices.len(), 4);
+    assert_eq!(ese.non_critical_errors.len(), 1);
+    assert_eq!(ese.non_critical_errors[0].1, "MSAW:BROKEN:high");
+    let inside = Position::new(52.75, -4.0).validate().unwrap();
+    let outside = Position::new(51.5, -0.5).validate().unwrap();
+
+    assert_eq!(ese.msaw_at(inside).map(|area| area.altitude), Some(5000));
+    assert!(ese.is_below_msaw(inside, 3000));
+    assert!(!ese.is_below_msaw(inside, 6000));
+    assert!(!ese.is_below_msaw(outside, 1000));
+}
+
+#[test]
+fn test_ese_radar_sites() {
+    let ese = r#"[RADAR]
+RADAR:HEATHROW:N051.28.39.000:W000.27.41.000:250:60:10000:45:250:60000:45:250:60000:45
+RADAR:CLEE HILL:N052.23.53.000:W002.35.38.000:1750:0:0:0:250:60000:60
+"#;
+    let ese = EseReader::new(ese.as_bytes()).try_read().unwrap();
+    assert!(ese.non_critical_errors.is_empty());
+    assert_eq!(ese.radar_sites.len(), 2);
+    assert_eq!(ese.radar_sites[0].primary.map(|mode| mode.range), Some(60));
+    assert!(ese.radar_sites[1].primary.is_none());
+    assert!(ese.radar_sites[1].secondary.is_none());
+    assert_eq!(ese.radar_sites[1].max_range(), Some(250));
+}
+
+#[test]
+fn test_coverage_rings() {
+    let ese = r#"[RADAR]
+RADAR:CLEE HILL:N052.23.53.000:W002.35.38.000:1750:0:0:0:250:60000:60
+"#;
+    let ese = EseReader::new(ese.as_bytes()).try_read().unwrap();
+    let sources = CoverageSource::from_es_radar_site(&ese.radar_sites[0]);
+    assert_eq!(sources.len(), 1);
+
+    // Low levels are limited by the radio horizon, high levels by the configured range
+    let low = sources[0].coverage_ring(10).unwrap();
+    let high = sources[0].coverage_ring(400).unwrap();
+    assert!(low.outer_radius.as_nautical_miles() < 100_f64);
+    assert!((high.outer_radius.as_nautical_miles() - 250_f64).abs() < 1e-6);
+    assert!(high.inner_radius.as_nautical_miles() > low.inner_radius.as_nautical_miles());
+    assert!(sources[0].coverage_ring(700).is_none());
+
+    // The cone of silence is a hole, which winds the opposite way to the outer ring
+    let map = AtcMap::try_from_coverage("sct".to_string(), &sources[0], &[400]).unwrap();
+    let AtcMapData::Embedded { features } = &map.data else {
+        panic!("Coverage maps are embedded");
+    };
+    let Some(geojson::Value::Polygon(rings)) = features.features[0].geometry.as_ref().map(|geometry| &geometry.value) else {
+        panic!("Coverage is a polygon");
+    };
+    let signed_area = |ring: &Vec<Vec<f64>>| ring.windows(2).map(|pair| pair[0][0] * pair[1][1] - pair[1][0] * pair[0][1]).sum::<f64>();
+    assert_eq!(rings.len(), 2);
+    assert!(signed_area(&rings[0]) * signed_area(&rings[1]) < 0_f64);
+}
+
+#[test]
+fn test_ese_procedure_queries() {
+    let ese = r#"[SIDSSTARS]
+SID:EGLL:27R:BPK7F:BPK
+SID:EGLL:09L:BPK7G:BPK
+SID:EGLL:27R:CPT3F:CPT
+STAR:EGLL:27R:BNN1A:BNN
+SID:egll:27R:DVR5F:LAM DVR
+STAR:EGLL:27R:OCK1A:TIMBA OCK
+"#;
+    let ese = EseReader::new(ese.as_bytes()).try_read().unwrap();
+    // Airports are grouped whatever the case of their identifier
+    assert_eq!(ese.sids_stars.len(), 1);
+    let egll = ese.airport("egll").unwrap();
+    let rwy_27r = "27R".parse().unwrap();
+
+    assert_eq!(egll.sids(&rwy_27r).iter().map(|sid| sid.identifier.as_str()).collect::<Vec<_>>(), vec!["BPK7F", "CPT3F", "DVR5F"]);
+    assert_eq!(egll.runways_for_procedure("bpk7f", ProcedureType::SID), vec![&rwy_27r]);
+    assert_eq!(egll.sid_for_route(&rwy_27r, "N0450F350 CPT DCT SAM").map(|sid| sid.identifier.as_str()), Some("CPT3F"));
+    assert_eq!(egll.star_for_route(&rwy_27r, "BPK7F BPK UN57 BNN").map(|star| star.identifier.as_str()), Some("BNN1A"));
+    // Procedures with more than one fix are matched on the fix that joins the route
+    assert_eq!(egll.sids(&rwy_27r).iter().find(|sid| sid.identifier == "DVR5F").unwrap().waypoints().collect::<Vec<_>>(), vec!["LAM", "DVR"]);
+    assert_eq!(egll.sid_for_route(&rwy_27r, "DVR UL9 KONAN").map(|sid| sid.identifier.as_str()), Some("DVR5F"));
+    assert_eq!(egll.star_for_route(&rwy_27r, "DVR UL9 TIMBA").map(|star| star.identifier.as_str()), Some("OCK1A"));
+}
+
+#[test]
+fn test_asr_freetext_sub_groups() {
+    let ese = "[FREETEXT]\nN051.28.20.000:W000.27.00.000:EGLL\\Stands:501\nN051.28.21.000:W000.27.01.000:EGLL\\Taxiways:A\n";
+    let ese = EseReader::new(ese.as_bytes()).try_read().unwrap();
+    assert_eq!(ese.free_text.iter().map(|group| group.path()).collect::<Vec<_>>(), vec![vec!["EGLL", "Stands"], vec!["EGLL", "Taxiways"]]);
+
+    let asr = EsAsr {
+        display_items: vec![DisplayItem {
+            item_type: SymbologyItemType::Label,
+            name: "EGLL\\Stands\\501".to_string(),
+            attribute: "freetext".to_string(),
+        }],
+        ..Default::default()
+    };
+    let display = AtcDisplay::from_es_asr("sct".to_string(), "prf".to_string(), asr);
+    assert!(matches!(&display.display_items[..], [AtcDisplayItem::Map { id, .. }] if id == "sct_free_text_EGLL\\Stands"));
+}
+
+#[test]
+fn test_squawk_allocation() {
+    let mut allocator = SquawkAllocator::default();
+    allocator.add_range("L".to_string(), SquawkRange::new(1776, 2001)).unwrap();
+    allocator.add_range("S".to_string(), SquawkRange::new(2001, 2005)).unwrap();
+    assert_eq!(allocator.add_range("X".to_string(), SquawkRange::new(1280, 1290)), Err(SquawkError::InvalidRange));
+
+    // 1777 is taken and 2000 is reserved, and 1778 / 1779 are not octal codes
+    let in_use = HashSet::from([1776, 1777]);
+    assert_eq!(allocator.allocate("L", &in_use, None), Ok(2001));
+    assert_eq!(allocator.allocate("L", &HashSet::from([1776, 1777, 2001]), None), Err(SquawkError::Exhausted));
+    assert_eq!(allocator.allocate("Q", &in_use, None), Err(SquawkError::UnknownPosition));
+    assert_eq!(allocator.overlaps().len(), 1);
+
+    // CRC banks of a subset are only used by positions of that subset
+    let bank = |id: &str, subset: Option<u32>, start: u32, end: u32| BeaconCodeBank {
+        id: id.to_string(),
+        subset,
+        start,
+        end,
+        ..Default::default()
+    };
+    let mut allocator = SquawkAllocator::default();
+    for bank in [bank("A", Some(1), 4601, 4677), bank("B", Some(2), 4701, 4777), bank("C", None, 5001, 5077)] {
+        allocator.add_range("ZNY".to_string(), SquawkRange::from_crc_bank(&bank).unwrap()).unwrap();
+    }
+    let in_use = HashSet::from([4701]);
+    assert_eq!(allocator.allocate_for_subset("ZNY", &in_use, None, 2), Ok(4702));
+    assert_eq!(allocator.allocate_for_subset("ZNY", &in_use, None, 1), Ok(4601));
+    assert_eq!(allocator.allocate_for_subset("ZNY", &in_use, None, 3), Ok(5001));
+    assert_eq!(allocator.allocate("ZNY", &in_use, None), Ok(4601));
+}
+
+const TEST_SCT: &str = r#"[INFO]
+Test
+TEST_CTR
+EGLL
+N051.28.39.000
+W000.27.41.000
+60
+39
+-1
+1
+[AIRPORT]
+egll 118.500 N051.28.39.000 W000.27.41.000 D
+[RUNWAY]
+09L 27R 090 270 N051.28.39.000 W000.29.06.000 N051.28.40.000 W000.26.02.000 egll
+[VOR]
+BPK 117.500 N051.44.59.000 W000.06.25.000
+"#;
+
+#[test]
+fn test_linked_airports_and_procedures() {
+    let sector = SctReader::new(TEST_SCT.as_bytes()).try_read().unwrap();
+    let ese = r#"[SIDSSTARS]
+SID:EGLL:27R:BPK7F:BPK
+SID:EGLL:27L:BPK7F:BPK
+SID:EGLL:09L:UMLA1G:UMLAT
+"#;
+    let ese = EseReader::new(ese.as_bytes()).try_read().unwrap();
+
+    let linked = LinkedAirports::new(&sector, &ese);
+    let egll = linked.airport("EGLL").unwrap();
+    assert_eq!(egll.runway(&"27R".parse().unwrap()).unwrap().procedures.len(), 1);
+    assert_eq!(linked.missing_runways.len(), 1);
+    assert_eq!(linked.missing_runways[0].runway.to_string(), "27L");
+
+    let resolution = ProcedureResolver::new(&sector, &ese).resolve();
+    let bpk7f = resolution.procedures.iter().find(|proc| proc.identifier == "BPK7F" && proc.runway.to_string() == "27R").unwrap();
+    assert_eq!(bpk7f.points.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>(), vec!["RW27R", "BPK"]);
+    assert!(resolution.unresolved.iter().any(|unresolved| unresolved.waypoint == "UMLAT"));
+    assert!(resolution.unresolved.iter().any(|unresolved| unresolved.waypoint == "RW27L"));
+}
+
+#[test]
+fn test_procedure_resolver() {
+    let sector = SctReader::new(TEST_SCT.as_bytes()).try_read().unwrap();
+    assert!(sector.find_waypoint("bpk").is_some());
+    let ese = r#"[SIDSSTARS]
+SID:EGLL:09L:BPK5K:bpk
+STAR:EGLL:27R:BPK1A:BPK LAM
+STAR:EGLL:27L:LAM1A:LAM
+"#;
+    let ese = EseReader::new(ese.as_bytes()).try_read().unwrap();
+    let resolution = ProcedureResolver::new(&sector, &ese).resolve();
+    assert_eq!(resolution.procedures.len(), 3);
+
+    let procedure = |identifier: &str| resolution.procedures.iter().find(|proc| proc.identifier == identifier).unwrap();
+    let sid = procedure("BPK5K");
+    assert_eq!(sid.proc_type, ProcedureType::SID);
+    assert_eq!(sid.points.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>(), vec!["RW09L", "bpk"]);
+
+    // STARs end at the threshold, and unknown fixes are left out
+    let star = procedure("BPK1A");
+    assert_eq!(star.points.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>(), vec!["BPK", "RW27R"]);
+    assert!(star.points[1].1.lon > sid.points[0].1.lon);
+
+    assert!(procedure("LAM1A").points.is_empty());
+    let mut unresolved = resolution.unresolved.iter().map(ToString::to_string).collect::<Vec<_>>();
+    unresolved.sort();
+    assert_eq!(
+        unresolved,
+        vec![
+            "Unknown waypoint LAM in BPK1A (EGLL 27R)",
+            "Unknown waypoint LAM in LAM1A (EGLL 27L)",
+            "Unknown waypoint RW27L in LAM1A (EGLL 27L)"
+        ]
+    );
+
+    // Procedures that cannot be drawn are reported by the package
+    let mut fs = test_memory_package();
+    fs.insert("/pkg/Sector/Test.ese", "[SIDSSTARS]\nSID:EGLL:27R:BPK7F:BPK\nSTAR:EGLL:27L:LAM1A:LAM\n");
+    let mut es = EuroScopeLoader::try_new_from_dir_with_fs(Arc::new(fs), "/pkg").unwrap();
+    let package = AtcScopePackage::try_from(es.try_read().unwrap()).unwrap();
+    assert_eq!(package.maps.keys().filter(|name| name.contains("_sids_") || name.contains("_stars_")).count(), 1);
+    assert_eq!(package.non_critical_errors.len(), 3);
+    assert!(package.non_critical_errors.last().unwrap().ends_with("LAM1A (EGLL 27L) has fewer than 2 known points and was not drawn"));
+}
+
+fn test_memory_package() -> MemoryFileSystem {
+    let mut fs = MemoryFileSystem::new();
+    fs.insert("/pkg/Test.prf", "Settings\tSettingsfileSYMBOLOGY\t\\Settings\\Symbology.txt\nSettings\tsector\t\\Sector\\Test.sct\nASRFastKeys\t1\t\\ASR\\Ground.asr\n");
+    fs.insert("/pkg/Settings/Symbology.txt", "m_ClipArea:5\nAirports:symbol:3947580:3.5:0:0:7\n");
+    fs.insert("/pkg/Sector/Test.sct", TEST_SCT);
+    fs.insert("/pkg/Sector/Test.ese", "[SIDSSTARS]\nSID:EGLL:27R:BPK7F:BPK\n");
+    fs.insert("/pkg/ASR/Ground.asr", "DisplayTypeName:Standard ES radar screen\nSECTORFILE:\nAirports:EGLL:symbol\n");
+    fs
+}
+
+#[test]
+fn test_load_es_from_memory_fs() {
+    let fs = test_memory_package();
+    assert!(fs.is_dir(Path::new("/pkg/Settings")));
+    assert_eq!(fs.canonicalize(Path::new("/pkg/Settings/../Sector/Test.sct")).unwrap(), Path::new("/pkg/Sector/Test.sct"));
+
+    let mut es = EuroScopeLoader::try_new_from_dir_with_fs(Arc::new(fs), "/pkg").unwrap();
+    assert_eq!(es.prfs.len(), 1);
+    assert_eq!(es.prfs[0].sector_file, "/pkg/Sector/Test.sct");
+
+    let result = es.try_read().unwrap();
+    let (sector, ese) = result.sectors.get("/pkg/Sector/Test.sct").unwrap();
+    assert_eq!(sector.airports.len(), 1);
+    assert!(ese.as_ref().unwrap().airport("EGLL").is_some());
+    assert_eq!(result.profiles[0].asrs.get("1").unwrap().sector_file_id.as_deref(), Some("/pkg/Sector/Test.sct"));
+}
+
+#[test]
+fn test_load_es_from_tar_fs() {
+    let memory = test_memory_package();
+    let mut builder = tar::Builder::new(Vec::new());
+    for path in memory.files() {
+        let mut contents = Vec::new();
+        memory.open(path).unwrap().read_to_end(&mut contents).unwrap();
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_cksum();
+        builder.append_data(&mut header, path.strip_prefix("/").unwrap(), contents.as_slice()).unwrap();
+    }
+    let archive = builder.into_inner().unwrap();
+
+    let fs = TarFileSystem::try_from_tar(archive.as_slice()).unwrap();
+    let mut es = EuroScopeLoader::try_new_from_dir_with_fs(Arc::new(fs), "/").unwrap();
+    let result = es.try_read().unwrap();
+    assert_eq!(result.profiles.len(), 1);
+    assert_eq!(result.profiles[0].prf_name, "Test");
+}
+
+#[test]
+fn test_es_path_resolver_roots() {
+    let mut fs = MemoryFileSystem::new();
+    fs.insert("/servers/es/UK/Data/Sector/Test.sct", TEST_SCT);
+    fs.insert("/servers/es/UK/Test.prf", "Settings\tsector\tuk\\data\\sector\\TEST.sct\n");
+
+    let resolver = EsPathResolver::new(vec!["/servers/other".into()]).with_root("/servers/es");
+    assert_eq!(
+        resolver.resolve(&fs, "/servers/es/UK/Test.prf", "UK\\Data\\Sector\\Test.sct").unwrap(),
+        Path::new("/servers/es/UK/Data/Sector/Test.sct")
+    );
+    // Without case-insensitive matching, a missing file falls back to the last root
+    assert_eq!(
+        resolver.resolve(&fs, "/servers/es/UK/Test.prf", "uk\\data\\sector\\TEST.sct").unwrap(),
+        Path::new("/servers/es/uk/data/sector/TEST.sct")
+    );
+
+    let resolver = resolver.with_case_insensitive(true);
+    assert_eq!(
+        resolver.resolve(&fs, "/servers/es/UK/Test.prf", "\\data\\SECTOR\\test.sct").unwrap(),
+        Path::new("/servers/es/UK/Data/Sector/Test.sct")
+    );
+    let prf = EuroScopeLoaderPrf::try_new_from_prf_with_resolver(&fs, &resolver, "/servers/es/UK/Test.prf").unwrap();
+    assert_eq!(prf.sector_file, "/servers/es/UK/Data/Sector/Test.sct");
+}
+
+#[test]
+fn test_load_es_from_zip() {
+    let files = [
+        ("EuroScope/UK/Test.prf", "Settings\tSettingsfileSYMBOLOGY\tUK\\Settings\\Symbology.txt\nSettings\tsector\tuk\\data\\sector\\test.sct\nASRFastKeys\t1\t\\ASR\\Ground.asr\n"),
+        ("EuroScope/UK/Settings/Symbology.txt", "m_ClipArea:5\n"),
+        ("EuroScope/UK/Data/Sector/Test.sct", TEST_SCT),
+        ("EuroScope/UK/ASR/Ground.asr", "DisplayTypeName:Standard ES radar screen\nSECTORFILE:\n"),
+    ];
+    let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    for (name, contents) in files {
+        writer.start_file(name, zip::write::SimpleFileOptions::default()).unwrap();
+        writer.write_all(contents.as_bytes()).unwrap();
+    }
+    let archive = writer.finish().unwrap();
+
+    let mut es = EuroScopeLoader::try_new_from_zip_reader(archive).unwrap();
+    assert_eq!(es.prfs.len(), 1);
+    assert_eq!(es.prfs[0].sector_file, "/EuroScope/UK/Data/Sector/Test.sct");
+    assert_eq!(es.prfs[0].asr_files[0].1, "/EuroScope/UK/ASR/Ground.asr");
+
+    let package = AtcScopePackage::try_from(es.try_read().unwrap()).unwrap();
+    assert_eq!(package.facilities.len(), 1);
+}
+
+#[test]
+fn test_prf_settings() {
+    let prf = "Settings\tSettingsfileSYMBOLOGY\t\\Settings\\Symbology.txt\r
+Settings\tSettingsfileTAGS\t\\Settings\\Tags.txt
+Settings\talias\t\\Data\\Alias.txt
+Settings\tsector\t\\Sector\\Test.sct
+Settings\tSettingsfileMYSTERY\t\\Settings\\Mystery.txt
+LastSession\tcallsign\tEGLL_N_APP
+LastSession\trating\t5
+LastSession\tserver\tAUTOMATIC
+LastSession\tpassword\tsecret
+RecentFiles\tRecent1\t\\Sector\\Test.sct
+ASRFastKeys\t3\t\\ASR\\Tower.asr
+ASRFastKeys\t1\t\\ASR\\Ground.asr
+Plugins\tPlugin0\t\\Plugins\\TopSky.dll
+Plugins\tPlugin0Display\tStandard ES radar screen
+Plugins\tPlugin0Disabled
+
+";
+    let settings = PrfSettings::try_from_reader(prf.as_bytes()).unwrap();
+    assert_eq!(settings.file(PrfSettingsFile::Symbology), Some("\\Settings\\Symbology.txt"));
+    assert_eq!(settings.file(PrfSettingsFile::Alias), Some("\\Data\\Alias.txt"));
+    assert_eq!(settings.last_session.callsign.as_deref(), Some("EGLL_N_APP"));
+    assert_eq!(settings.last_session.rating, Some(5));
+    assert_eq!(settings.recent_files, vec!["\\Sector\\Test.sct"]);
+    assert_eq!(settings.asr_fast_keys.iter().map(|(key, _)| key.as_str()).collect::<Vec<_>>(), vec!["3", "1"]);
+    assert_eq!(settings.plugins, vec!["\\Plugins\\TopSky.dll"]);
+    assert_eq!(settings.unknown_value("settings", "settingsfilemystery"), Some("\\Settings\\Mystery.txt"));
+    assert_eq!(settings.unknown_value("Plugins", "Plugin0Display"), Some("Standard ES radar screen"));
+    assert!(settings.unknown.iter().all(|(_, key, _)| key != "password"));
+    // Lines with fewer than 3 fields are kept, blank lines are not
+    assert_eq!(settings.unknown_value("plugins", "plugin0disabled"), Some(""));
+    assert!(settings.unknown.iter().all(|(section, _, _)| !section.is_empty()));
+
+    let mut fs = test_memory_package();
+    fs.insert("/pkg/Test.prf", prf);
+    fs.insert("/pkg/Settings/Tags.txt", "");
+    let loaded = EuroScopeLoaderPrf::try_new_from_prf_with_fs(&fs, "/pkg/Test.prf").unwrap();
+    assert_eq!(loaded.settings_files.get(&PrfSettingsFile::Tags).map(String::as_str), Some("/pkg/Settings/Tags.txt"));
+    assert!(!loaded.settings_files.contains_key(&PrfSettingsFile::Alias));
+    assert_eq!(loaded.asr_files.len(), 1);
+}
+
+#[test]
+fn test_alias_expansion() {
+    let alias = "; Standard phraseology
+.ils $uc, cleared ILS approach runway $1, QNH $altim($arr)
+.hello $radioname($uc) , $callsign, hello
+.ILS $uc, cleared ILS runway $arrrwy($arr)
+
+not an alias
+";
+    let alias = AliasFile::try_from_reader(alias.as_bytes(), "alias.txt").unwrap();
+    assert_eq!(alias.aliases.len(), 3);
+    assert_eq!(alias.get(".ils").unwrap().line, 4);
+    // Latin-1 text is kept rather than dropping the alias
+    let latin1 = AliasFile::try_from_reader(&b".sv Montr\xe9al radar\n.bye Good day\n"[..], "alias.txt").unwrap();
+    assert_eq!(latin1.aliases.len(), 2);
+    assert_eq!(latin1.get("sv").unwrap().text, "Montr\u{fffd}al radar");
+
+    let context = HashMap::from([
+        ("uc".to_string(), "BAW123".to_string()),
+        ("arr".to_string(), "EGLL".to_string()),
+        ("arrrwy(EGLL)".to_string(), "27R".to_string()),
+        ("altim".to_string(), "1013".to_string()),
+    ]);
+    let expander = AliasExpander::new(&alias);
+    assert_eq!(expander.expand_command(".ils", &context).unwrap().text, "BAW123, cleared ILS runway 27R");
+
+    let expanded = AliasExpander::expand_text(&alias.aliases[0].text, &["09L"], &context);
+    assert_eq!(expanded.text, "BAW123, cleared ILS approach runway 09L, QNH 1013");
+
+    let expanded = expander.expand_command(".hello", &context).unwrap();
+    assert_eq!(expanded.text, "$radioname($uc) , $callsign, hello");
+    assert_eq!(expanded.unresolved, vec!["radioname($uc)", "callsign"]);
+    assert!(expander.expand_command(".missing", &context).is_none());
+}
+
+#[test]
+fn test_icao_reference_data() {
+    let mut fs = test_memory_package();
+    fs.insert("/pkg/Test.prf", "Settings\tSettingsfileSYMBOLOGY\t\\Settings\\Symbology.txt\nSettings\tsector\t\\Sector\\Test.sct\nSettings\tairlines\t\\Data\\ICAO_Airlines.txt\nSettings\taircraft\t\\Data\\ICAO_Aircraft.txt\n");
+    fs.insert("/pkg/Data/ICAO_Airlines.txt", b";comment\nBAW\tBRITISH AIRWAYS\tSPEEDBIRD\tUNITED KINGDOM\r\nAFR\tAIR FRANCE\tAIRFRANS\tFRANCE\nIBE\tIBERIA L\xcdNEAS\tIBERIA\tSPAIN\n".to_vec());
+    fs.insert("/pkg/Data/ICAO_Aircraft.txt", "B738\tML2J\tBOEING\t737-800\nA388\tJL4J\tAIRBUS\tA-380-800\n");
+
+    let mut es = EuroScopeLoader::try_new_from_dir_with_fs(Arc::new(fs), "/pkg").unwrap();
+    let package = AtcScopePackage::try_from(es.try_read().unwrap()).unwrap();
+    let reference = package.reference_data.unwrap();
+
+    assert_eq!(reference.telephony("BAW123").as_deref(), Some("SPEEDBIRD 123"));
+    assert_eq!(reference.airline_for_callsign("afr1234").unwrap().country, "FRANCE");
+    assert_eq!(reference.airline_for_callsign("IBE3456").unwrap().telephony, "IBERIA");
+    assert!(reference.telephony("G-ABCD").is_none());
+    let a388 = reference.aircraft_type("a388").unwrap();
+    assert_eq!(a388.wake_category.as_deref(), Some("J"));
+    assert_eq!(a388.engine_count, Some(4));
+    assert_eq!(a388.engine_type.as_deref(), Some("J"));
+    assert!(reference.airports.is_empty());
+}
+
+#[test]
+fn test_navdata_airways_and_intersections() {
+    let airways = "A\t51.0\t-1.0\t14\tUL9\tH\t\t\t\t\t\t\tB\t51.0\t0.0\t0\t0\tY
+B\t51.0\t0.0\t14\tUL9\tH\tA\t51.0\t-1.0\t0\t0\tN\tC\t51.0\t1.0\t0\t0\tY
+C\t51.0\t1.0\t14\tUL9\tH\tB\t51.0\t0.0\t0\t0\tN\t\t\t\t\t\t
+A\t51.0\t-1.0\t14\tUN1\tB\t\t\t\t\t\t\tD\t52.0\t0.0\t250\t0\tY
+D\t52.0\t0.0\t14\tUN1\tB\tA\t51.0\t-1.0\t250\t0\tY\tC\t51.0\t1.0\t250\t0\tY
+C\t51.0\t1.0\t14\tUN1\tB\tD\t52.0\t0.0\t250\t0\tY\t\t\t\t\t\t
+X\tfoo
+";
+    // Comments are skipped and lines that are not valid UTF-8 are still read
+    let isec = b"MIDHU\t-30.0\t20.0\t18\nMIDHU\t51.5\t-0.5\t18\n// Exported fixes\nBROKEN\nK\xc9LA\t48.0\t2.0\t18\n";
+
+    let mut navdata = NavData::default();
+    navdata.try_load_airways(airways.as_bytes()).unwrap();
+    navdata.try_load_isec(&isec[..]).unwrap();
+    assert_eq!(navdata.non_critical_errors.len(), 2);
+    assert_eq!(navdata.intersections.len(), 3);
+
+    let route = navdata.airways.find_route("A", "C", None).unwrap();
+    assert_eq!(route.fixes.iter().map(|fix| fix.identifier.as_str()).collect::<Vec<_>>(), vec!["A", "B", "C"]);
+    assert_eq!(route.airways, vec!["UL9", "UL9"]);
+    let back = navdata.airways.find_route("C", "A", Some(300)).unwrap();
+    assert_eq!(back.fixes.iter().map(|fix| fix.identifier.as_str()).collect::<Vec<_>>(), vec!["C", "D", "A"]);
+    assert!(navdata.airways.find_route("C", "A", Some(200)).is_none());
+    assert!(navdata.airways.expand_airway("UL9", "C", "A").is_none());
+    assert_eq!(navdata.airways.expand_airway("ul9", "a", "c").unwrap().fixes.len(), 3);
+
+    let sct = format!("{}[HIGH AIRWAY]\nUL9 MIDHU MIDHU BPK BPK\n", TEST_SCT);
+    let sector = SctReader::new(sct.as_bytes())
+        .with_waypoint_index(Arc::new(navdata.intersections))
+        .try_read()
+        .unwrap();
+    // The MIDHU closest to the sector centre is used
+    assert_eq!(sector.high_airways[0].lines[0].line.start.lat, 51.5);
+}
+
+#[test]
+fn test_route_expansion() {
+    let sector = SctReader::new(TEST_SCT.as_bytes()).try_read().unwrap();
+    let ese = r#"[SIDSSTARS]
+SID:EGLL:27R:BPK7F:BPK
+SID:EGLL:27R:ABC1F:BPK A
+SID:EGLL:09L:ABC1F:A
+[AIRSPACE]
+SECTORLINE:LW
+COORD:N053.00.00.000:W002.00.00.000
+COORD:N053.00.00.000:W000.18.00.000
+COORD:N050.00.00.000:W000.18.00.000
+COORD:N050.00.00.000:W002.00.00.000
+COORD:N053.00.00.000:W002.00.00.000
+SECTORLINE:LE_A
+COORD:N053.00.00.000:W000.18.00.000
+COORD:N053.00.00.000:E002.00.00.000
+COORD:N050.00.00.000:E002.00.00.000
+SECTORLINE:LE_B
+COORD:N053.00.00.000:W000.18.00.000
+COORD:N050.00.00.000:W000.18.00.000
+COORD:N050.00.00.000:E002.00.00.000
+SECTOR:LON_W:0:66000
+OWNER:LON_W:LON_C
+BORDER:LW
+SECTOR:LON_E:0:66000
+OWNER:LON_E:LON_C
+BORDER:LE_A:LE_B
+"#;
+    let ese = EseReader::new(ese.as_bytes()).try_read().unwrap();
+    assert_eq!(ese.sectors[0].owners, vec!["LON_W", "LON_C"]);
+    assert_eq!(ese.sector_polygon(&ese.sectors[1]).len(), 5);
+    let inside = Position::new(51.0, 1.0).validate().unwrap();
+    assert_eq!(ese.sectors_at(inside, Some(35000)).iter().map(|sector| sector.name.as_str()).collect::<Vec<_>>(), vec!["LON_E"]);
+
+    let airways = "A\t51.0\t-1.0\t14\tUL9\tH\t\t\t\t\t\t\tB\t51.0\t0.0\t0\t0\tY
+B\t51.0\t0.0\t14\tUL9\tH\tA\t51.0\t-1.0\t0\t0\tN\tC\t51.0\t1.0\t0\t0\tY
+C\t51.0\t1.0\t14\tUL9\tH\tB\t51.0\t0.0\t0\t0\tN\t\t\t\t\t\t
+";
+    let mut navdata = NavData::default();
+    navdata.try_load_airways(airways.as_bytes()).unwrap();
+
+    let route = RouteExpander::new()
+        .with_sector(&sector)
+        .with_ese(&ese)
+        .with_navdata(&navdata)
+        .expand(Some("EGLL"), "N0450F350 BPK7F BPK DCT 5130N00010W UNKWN A UL9 C/N0440F370", None);
+    assert_eq!(
+        route.waypoints.iter().map(|waypoint| waypoint.name.as_str()).collect::<Vec<_>>(),
+        vec!["EGLL", "BPK", "5130N00010W", "A", "B", "C"]
+    );
+    assert_eq!(route.waypoints[1].via.as_deref(), Some("BPK7F"));
+    assert_eq!(route.waypoints[4].via.as_deref(), Some("UL9"));
+    assert_eq!(route.waypoints[2].level, Some(35000));
+    assert_eq!(route.unexpanded.len(), 1);
+    assert_eq!(route.unexpanded[0].element, "UNKWN");
+    assert_eq!(route.unexpanded[0].reason, UnexpandedReason::UnknownWaypoint);
+    assert_eq!(route.sectors, vec!["LON_W", "LON_E", "LON_W", "LON_E"]);
+
+    // A SID with more than one fix, which has a different route for each runway
+    let expander = RouteExpander::new().with_sector(&sector).with_ese(&ese).with_navdata(&navdata);
+    let rwy_27r = "27R".parse().unwrap();
+    let route = expander.expand_for_runways(Some("EGLL"), Some(&rwy_27r), "ABC1F B", None, None);
+    assert_eq!(route.waypoints.iter().map(|waypoint| waypoint.name.as_str()).collect::<Vec<_>>(), vec!["EGLL", "BPK", "A", "B"]);
+    assert_eq!(route.waypoints[2].via.as_deref(), Some("ABC1F"));
+    assert!(route.unexpanded.is_empty());
+    let route = expander.expand(Some("EGLL"), "ABC1F B", None);
+    assert_eq!(route.unexpanded[0].element, "ABC1F");
+    assert_eq!(route.unexpanded[0].reason, UnexpandedReason::AmbiguousProcedure);
+
+    let broken = RouteExpander::new().with_navdata(&navdata).expand(None, "C UL9 A", None);
+    assert_eq!(broken.unexpanded[0].reason, UnexpandedReason::AirwayNotConnected);
+
+    // Elements that are not ASCII are unknown waypoints, whatever their length in bytes
+    let route = RouteExpander::new()
+        .with_navdata(&navdata)
+        .expand(None, "A/N045É ÉN010W 51ÉN00010W C/M08É", None);
+    assert_eq!(route.waypoints.iter().map(|waypoint| waypoint.name.as_str()).collect::<Vec<_>>(), vec!["A", "C"]);
+    assert_eq!(route.waypoints[0].level, None);
+    assert_eq!(route.unexpanded.len(), 2);
+    assert!(route.unexpanded.iter().all(|unexpanded| unexpanded.reason == UnexpandedReason::UnknownWaypoint));
+}
+
+#[test]
+fn test_ese_sectors() {
+    let ese = r#"[AIRSPACE]
+SECTORLINE:NORTH
+COORD:N052.00.00.000:W001.00.00.000
+COORD:N052.00.00.000:E001.00.00.000
+SECTORLINE:SOUTH
+COORD:N051.00.00.000:W001.00.00.000
+COORD:N051.00.00.000:E001.00.00.000
+SECTORLINE:WEST
+COORD:N052.00.00.000:W001.00.00.000
+COORD:N051.00.00.000:W001.00.00.000
+SECTORLINE:EAST
+COORD:N052.00.00.000:E001.00.00.000
+COORD:N051.00.00.000:E001.00.00.000
+CIRCLE_SECTORLINE:CTR:N051.30.00.000:E000.00.00.000:5
+SECTOR:LON_C:0:24500
+OWNER:LON_C:LON_S
+BORDER:NORTH:EAST:SOUTH:WEST
+SECTOR:LON_CTR:0:high
+OWNER:LON_X
+SECTOR:LTC_CTR:0:5000
+OWNER:LTC
+BORDER:CTR
+"#;
+    let ese = EseReader::new(ese.as_bytes()).try_read().unwrap();
+    assert_eq!(ese.sector_lines.len(), 5);
+    assert_eq!(ese.sector_line("CTR").unwrap().points.len(), 36);
+    assert_eq!(ese.sectors.iter().map(|sector| sector.name.as_str()).collect::<Vec<_>>(), vec!["LON_C", "LTC_CTR"]);
+
+    // The invalid sector is reported and its owner is not given to LON_C
+    assert_eq!(ese.non_critical_errors.len(), 2);
+    assert_eq!(ese.non_critical_errors[0].1, "SECTOR:LON_CTR:0:high");
+    assert_eq!(ese.sectors[0].owners, vec!["LON_C", "LON_S"]);
+
+    // The border lines are joined end to end, reversing those that run the other way
+    let polygon = ese.sector_polygon(&ese.sectors[0]);
+    assert_eq!(polygon.len(), 5);
+    assert_eq!(polygon.first().map(|point| (point.lat, point.lon)), polygon.last().map(|point| (point.lat, point.lon)));
+
+    let centre = Position::new(51.5, 0.0).validate().unwrap();
+    let names = |altitude: Option<u32>| ese.sectors_at(centre, altitude).iter().map(|sector| sector.name.as_str()).collect::<Vec<_>>();
+    assert_eq!(names(None), vec!["LON_C", "LTC_CTR"]);
+    assert_eq!(names(Some(10000)), vec!["LON_C"]);
+    assert!(ese.sectors_at(Position::new(53.0, 0.0).validate().unwrap(), None).is_empty());
+}
+
+#[test]
+fn test_topsky_maps() {
+    let mut fs = test_memory_package();
+    fs.insert(
+        "/pkg/Test.prf",
+        "Settings\tSettingsfileSYMBOLOGY\t\\Settings\\Symbology.txt\nSettings\tsector\t\\Sector\\Test.sct\nPlugins\tPlugin0\t\\TopSky\\TopSky.dll\n",
+    );
+    fs.insert("/pkg/TopSky/TopSky.dll", "");
+    fs.insert("/pkg/TopSky/TopSkySettings.txt", "Color_Stands=10,20,30\nSetup_Foo=1\n");
+    fs.insert(
+        "/pkg/TopSky/TopSkyMaps.txt",
+        r#"// Test maps
+COLORDEF:Stands:255:0:0
+COLORDEF:Centreline:0:255:0
+SYMBOLDEF:VOR
+LINE:-3:-3:3:3
+
+MAP:Stands
+FOLDER:EGLL
+COLOR:Stands
+ACTIVE:1
+COORD:N051.28.00.000:W000.28.00.000
+COORD:N051.28.00.000:W000.27.00.000
+COORD:BPK
+COORDPOLY:50
+TEXT:BPK:Brookmans Park
+LINE:FOO:BAR
+
+MAP:Centrelines
+FOLDER:EGLL
+COLOR:Centreline
+ACTIVE:RWY:ARR:EGLL27R,EGLL27L:DEP:*
+LINE:N051.28.39.000:W000.29.06.000:BPK
+CIRCLE:BPK:5:30
+CIRCLE:BPK:5:0.0001
+SYMBOL:VOR:BPK:BPK
+"#,
+    );
+
+    let mut es = EuroScopeLoader::try_new_from_dir_with_fs(Arc::new(fs), "/pkg").unwrap();
+    let result = es.try_read().unwrap();
+    let topsky = result.profiles[0].topsky_maps.as_ref().unwrap();
+    assert_eq!(topsky.maps.len(), 2);
+    // The line with unknown points and the circle with too small a step
+    assert_eq!(topsky.non_critical_errors.iter().map(|(line_number, _, _)| *line_number).collect::<Vec<_>>(), vec![16, 24]);
+    assert_eq!(topsky.folders().get("EGLL").map(|maps| maps.len()), Some(2));
+    assert!(topsky.maps[0].is_always_active());
+    assert!(matches!(&topsky.maps[0].items[0], TopSkyMapItem::Polygon { points, fill: 50, .. } if points.len() == 3));
+    assert_eq!(
+        topsky.maps[1].active,
+        vec![TopSkyActive::Runway {
+            arrival: vec!["EGLL27R".to_string(), "EGLL27L".to_string()],
+            departure: vec!["*".to_string()]
+        }]
+    );
+    assert!(matches!(&topsky.maps[1].items[1], TopSkyMapItem::Line { points, .. } if points.len() == 13));
+    // TopSkySettings.txt colours replace those of COLORDEF
+    assert_eq!(topsky.colours.get("stands").map(|colour| (colour.r, colour.g, colour.b)), Some((10, 20, 30)));
+
+    let package = AtcScopePackage::try_from(result).unwrap();
+    let map = package.maps.get("/pkg/Test.prf_topsky_EGLL\\Centrelines").unwrap();
+    let AtcMapData::Embedded { features } = &map.data else {
+        panic!("TopSky maps are embedded");
+    };
+    assert_eq!(features.features.len(), 3);
+    let props = features.features[0].properties.as_ref().unwrap();
+    assert_eq!(props.get("color").unwrap(), "#00FF00");
+    assert_eq!(features.features[2].properties.as_ref().unwrap().get("symbol").unwrap(), "VOR");
+}
+
+#[test]
+fn test_ground_plugin_maps() {
+    let mut fs = test_memory_package();
+    fs.insert(
+        "/pkg/Test.prf",
+        "Settings\tSettingsfileSYMBOLOGY\t\\Settings\\Symbology.txt\nSettings\tsector\t\\Sector\\Test.sct\nPlugins\tPlugin0\t\\GRplugin\\GRplugin.dll\n",
+    );
+    fs.insert("/pkg/GRplugin/GRplugin.dll", "");
+    fs.insert(
+        "/pkg/GRplugin/GRpluginStands.txt",
+        r#"// Heathrow
+STAND:EGLL:501:N051.28.00.000:W000.29.00.000:30
+WTC:LMH
+USE:C
+CODE:ABCDE
+STAND:EGLL:502:N051.28.01.000:W000.29.00.000
+COORD:N051.28.01.000:W000.29.01.000
+COORD:N051.28.02.000:W000.29.01.000
+COORD:N051.28.02.000:W000.29.00.000
+STAND:EGLL:BAD
+"#,
+    );
+    fs.insert(
+        "/pkg/GRplugin/GRpluginMaps.txt",
+        r#"APRON:EGLL:Terminal 5
+COORD:N051.28.00.000:W000.29.00.000
+COORD:N051.28.10.000:W000.29.00.000
+COORD:N051.28.10.000:W000.28.50.000
+TAXIWAY:EGLL:A
+COORD:N051.28.00.000:W000.29.00.000
+"#,
+    );
+
+    let mut es = EuroScopeLoader::try_new_from_dir_with_fs(Arc::new(fs), "/pkg").unwrap();
+    let result = es.try_read().unwrap();
+    let ground = result.profiles[0].ground_maps.as_ref().unwrap();
+    assert_eq!(ground.stands.len(), 2);
+    assert_eq!(ground.stands[0].wake_categories.as_deref(), Some("LMH"));
+    assert_eq!(ground.stands[0].outline().len(), 13);
+    assert_eq!(ground.stands[1].outline().len(), 3);
+    assert_eq!(ground.areas.len(), 1);
+    assert_eq!(ground.areas[0].area_type, GroundAreaType::Apron);
+    // The invalid stand and the taxiway with a single point
+    assert_eq!(ground.non_critical_errors.iter().map(|(line_number, _, _)| *line_number).collect::<Vec<_>>(), vec![5, 10]);
+    assert_eq!(
+        result.profiles[0].report.line_errors.iter().map(|(file, line_number, _, _)| (file.as_str(), *line_number)).collect::<Vec<_>>(),
+        vec![("/pkg/GRplugin/GRpluginMaps.txt", 5), ("/pkg/GRplugin/GRpluginStands.txt", 10)]
+    );
+    assert_eq!(ground.airports(), vec!["EGLL"]);
+
+    let package = AtcScopePackage::try_from(result).unwrap();
+    assert!(package.maps.contains_key("/pkg/Test.prf_apron_EGLL"));
+    let AtcMapData::Embedded { features } = &package.maps.get("/pkg/Test.prf_stand_EGLL").unwrap().data else {
+        panic!("Stand maps are embedded");
+    };
+    assert_eq!(features.features.len(), 2);
+    let stand = package.symbols.get("/pkg/Test.prf_stand_EGLL_501").unwrap();
+    assert_eq!(stand.feature.properties.as_ref().unwrap().get("use").unwrap(), "C");
+    let ground_night = package.display_types.get("ground-night").unwrap();
+    assert!(ground_night.map_defaults.contains_key("stand"));
+    assert_eq!(ground_night.symbol_defaults.get("stand").map(|(symbol, _)| symbol.color), Some(Colour::new(24, 66, 110)));
+}
+
+#[test]
+fn test_tag_datablock_layouts() {
+    let mut fs = test_memory_package();
+    fs.insert(
+        "/pkg/Test.prf",
+        "Settings\tSettingsfileSYMBOLOGY\t\\Settings\\Symbology.txt\nSettings\tSettingsfileTAGS\t\\Settings\\Tags.txt\nSettings\tsector\t\\Sector\\Test.sct\n",
+    );
+    fs.insert("/pkg/Settings/Symbology.txt", "m_ClipArea:5\nDatablock:assumed:65280:3.5:0:0:7\nDatablock:non-concerned:8421504:3.5:0:0:7\n");
+    fs.insert(
+        "/pkg/Settings/Tags.txt",
+        r#"TAGITEM:Callsign
+TAGFAMILY:Test Radar
+TAGTYPE:Assumed
+TAGITEM:Callsign:0:1:Open FP dialog:Handoff
+TAGITEM:Assigned Squawk
+TAGLINE
+TAGITEM:Altitude:255
+TAGITEM:Ground Speed
+TAGTYPE:Assumed:DETAILED
+TAGITEM:Callsign
+TAGLINE
+TAGITEM:Aircraft Type:red
+TAGITEM:Route
+TAGLINE
+"#,
+    );
+
+    let mut es = EuroScopeLoader::try_new_from_dir_with_fs(Arc::new(fs), "/pkg").unwrap();
+    let result = es.try_read().unwrap();
+    let tags = result.profiles[0].tags.as_ref().unwrap();
+    // The item before any family and the item with an invalid colour
+    assert_eq!(tags.non_critical_errors.iter().map(|error| error.0).collect::<Vec<_>>(), vec![1, 12]);
+    let family = tags.family("test radar").unwrap();
+    assert_eq!(family.definition("assumed", false).unwrap().lines.len(), 2);
+    assert_eq!(family.definition("assumed", true).unwrap().lines.len(), 2);
+
+    let package = AtcScopePackage::try_from(result).unwrap();
+    let datablock = &package.display_types.get("/pkg/Test.prf").unwrap().datablock;
+    assert_eq!(datablock.colors.get("assumed").map(|colour| colour.g), Some(255));
+    let layout = datablock.layout("Test Radar", "Assumed", false).unwrap();
+    assert_eq!(layout.lines[0][0].item_type, "Callsign");
+    assert!(layout.lines[0][0].bold);
+    assert!(layout.lines[0][0].color.is_none());
+    assert_eq!(layout.lines[0][0].right_click.as_deref(), Some("Handoff"));
+    assert_eq!(layout.lines[1][0].color.map(|colour| colour.r), Some(255));
+    assert_eq!(layout.lines[1][1].item_type, "Ground Speed");
+}
+
+#[test]
+fn test_screen_and_list_settings() {
+    let mut fs = test_memory_package();
+    fs.insert(
+        "/pkg/Test.prf",
+        "Settings\tSettingsfileSYMBOLOGY\t\\Settings\\Symbology.txt\nSettings\tSettingsfileSCREEN\t\\Settings\\Screen.txt\nSettings\tSettingsfileLISTS\t\\Settings\\Lists.txt\nSettings\tsector\t\\Sector\\Test.sct\n",
+    );
+    fs.insert("/pkg/Settings/Screen.txt", "m_ShowCompass:1\nm_TagFamily:Test Radar\nBROKEN\n");
+    fs.insert(
+        "/pkg/Settings/Lists.txt",
+        r#"POSITION:0:0
+LIST:DEPARTURE:Departures EGLL
+POSITION:10:20:400:200
+SORT:EOBT:DESC
+COLUMN:C/S:Callsign:8
+COLUMN:SID:Assigned SID:7
+LIST:SIL:Inbound
+VISIBLE:0
+COLUMN:C/S
+"#,
+    );
+
+    let mut es = EuroScopeLoader::try_new_from_dir_with_fs(Arc::new(fs), "/pkg").unwrap();
+    let result = es.try_read().unwrap();
+    let profile = &result.profiles[0];
+    assert_eq!(profile.screen_settings.as_ref().unwrap().get("m_showcompass"), Some("1"));
+    let lists = profile.list_settings.as_ref().unwrap();
+    assert_eq!(lists.non_critical_errors.iter().map(|error| error.0).collect::<Vec<_>>(), vec![1, 9]);
+    let departures = lists.list(&EsListKind::Departure).unwrap();
+    assert_eq!(departures.size, Some((400, 200)));
+    assert_eq!(departures.columns.len(), 2);
+
+    let package = AtcScopePackage::try_from(result).unwrap();
+    let layout = package.facilities[0].layout.as_ref().unwrap();
+    assert_eq!(layout.options.get("m_TagFamily").map(String::as_str), Some("Test Radar"));
+    let departures = layout.list("departure").unwrap();
+    assert_eq!(departures.position, Some((10, 20)));
+    assert_eq!(departures.sort_column.as_deref(), Some("EOBT"));
+    assert!(departures.sort_descending);
+    assert_eq!(departures.columns[1].item, "Assigned SID");
+    assert!(!layout.list("sil").unwrap().visible);
+}
+
+#[test]
+fn test_symbology_diagnostics() {
+    let symbology = r#"SYMBOLOGY
+SYMBOLSIZE
+m_ClipArea:8
+m_FontName:Euroscope
+m_FontSize:11.5
+Airports:symbol:3947580:3.5:0:0:7
+Airports:name:3947580:3.5:0:0:7
+Fixes:symbol:3947580
+Vors:symbol:blue:3.5:0:0:7
+Sector Inactive Name:active:255:3.5:0:1:7
+SYMBOL:x
+SYMBOLITEM:MOVETO 0 0
+SYMBOL:0
+SYMBOLITEM:MOVETO -2 -2
+"#;
+    let symbology = SymbologyInfo::try_from_reader(symbology.as_bytes(), "Symbology.txt").unwrap();
+    assert_eq!(symbology.clipping_area, 8);
+    assert_eq!(symbology.font_name.as_deref(), Some("Euroscope"));
+    assert_eq!(symbology.font_size, Some(11.5));
+    assert_eq!(symbology.non_critical_errors.iter().map(|error| error.0).collect::<Vec<_>>(), vec![8, 9, 11, 12]);
+    assert_eq!(symbology.item(&SymbologyItemType::Airports).unwrap().defs.len(), 2);
+    let unknown = SymbologyItemType::Unknown("Sector Inactive Name".to_string());
+    assert_eq!(symbology.item(&unknown).unwrap().defs[0].line_weight, 1);
+    assert_eq!(unknown.to_key_string(), "sector_inactive_name");
+    assert_eq!(symbology.symbol_icons, vec![(0, vec!["MOVETO -2 -2".to_string()])]);
+}
+
+#[test]
+fn test_asr_item_mapping() {
+    let mut fs = test_memory_package();
+    fs.insert(
+        "/pkg/Sector/Test.ese",
+        "[AIRSPACE]\nSECTORLINE:LL\nCOORD:N051.00.00.000:W001.00.00.000\nCOORD:N052.00.00.000:W001.00.00.000\nCOORD:N052.00.00.000:E000.00.00.000\nCOORD:N051.00.00.000:W001.00.00.000\nSECTOR:LON_S:0:24500\nOWNER:LON_S\nBORDER:LL\n",
+    );
+    fs.insert(
+        "/pkg/ASR/Ground.asr",
+        r#"DisplayTypeName:Standard ES radar screen
+SECTORFILE:
+Airports:EGLL:symbol
+Airports:EGLL:name
+Vors:BPK:symbol
+Vors:BPK:name
+Fixes:MIDHU:symbol
+Runways:EGLL 09L-27R:centerline
+Runways:EGLL 09L-27R:extended centerline
+Runways:EGLL 09L-27R:name
+Sids:EGLL BPK7F:line
+Sids:EGLL BPK7F:name
+Sector:LON_S:active
+Metar:EGLL:metar
+Controller:EGLL_TWR:name
+Datablock:Assumed:show
+Holding Areas:BNN:line
+PLUGIN:TopSky plugin:ShowMap:EGLL Stands:1
+Truncated
+"#,
+    );
+
+    let mut es = EuroScopeLoader::try_new_from_dir_with_fs(Arc::new(fs), "/pkg").unwrap();
+    let result = es.try_read().unwrap();
+    let package = AtcScopePackage::try_from(result).unwrap();
+    assert!(package.maps.contains_key("/pkg/Sector/Test.sct_sector_LON_S"));
+
+    let display = &package.facilities[0].displays[0];
+    let navdata = display
+        .display_items
+        .iter()
+        .filter_map(|item| match item {
+            AtcDisplayItem::NavdataItem { symbol_type, ident, show_symbol, show_label } => Some((symbol_type.as_str(), ident.as_str(), *show_symbol, *show_label)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        navdata,
+        vec![
+            ("vors", "BPK", true, true),
+            ("fixes", "MIDHU", true, false),
+            ("runways", "EGLL 09L-27R", true, true),
+            ("runway_extended_centerlines", "EGLL 09L-27R", true, false),
+        ]
+    );
+    let maps = display
+        .display_items
+        .iter()
+        .filter_map(|item| match item {
+            AtcDisplayItem::Map { id, .. } => Some(id.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(maps, vec!["/pkg/Sector/Test.sct_sids_EGLL BPK7F", "/pkg/Sector/Test.sct_sector_LON_S"]);
+    assert!(matches!(&display.display_items[0], AtcDisplayItem::Symbol { show_symbol: true, show_label: true, .. }));
+
+    assert_eq!(display.settings.len(), 5);
+    assert!(display.settings.contains(&AtcDisplaySetting::Item {
+        item_type: "metar".to_string(),
+        name: "EGLL".to_string(),
+        attribute: "metar".to_string()
+    }));
+    assert!(display.settings.contains(&AtcDisplaySetting::Item {
+        item_type: "holding_areas".to_string(),
+        name: "BNN".to_string(),
+        attribute: "line".to_string()
+    }));
+    assert_eq!(
+        display.settings.last(),
+        Some(&AtcDisplaySetting::Plugin {
+            plugin: "TopSky plugin".to_string(),
+            key: "ShowMap".to_string(),
+            value: "EGLL Stands:1".to_string()
+        })
+    );
+}
+
+/// A [`MemoryFileSystem`] whose `unreadable` files exist but cannot be opened.
+#[derive(Debug)]
+struct UnreadableFileSystem {
+    inner: MemoryFileSystem,
+    unreadable: Vec<PathBuf>,
+}
+
+impl FileSystem for UnreadableFileSystem {
+    fn open(&self, path: &Path) -> io::Result<Box<dyn BufRead + '_>> {
+        if self.unreadable.iter().any(|unreadable| unreadable == path) {
+            return Err(io::Error::new(io::ErrorKind::PermissionDenied, "Permission denied"));
+        }
+        self.inner.open(path)
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.inner.exists(path)
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        self.inner.is_dir(path)
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        self.inner.read_dir(path)
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        self.inner.canonicalize(path)
+    }
+}
+
+#[test]
+fn test_profile_reports() {
+    let mut fs = test_memory_package();
+    fs.insert(
+        "/pkg/Test.prf",
+        "Settings\tSettingsfileSYMBOLOGY\t\\Settings\\Symbology.txt\nSettings\tsector\t\\Sector\\Test.sct\nASRFastKeys\t1\t\\ASR\\Ground.asr\nASRFastKeys\t2\t\\ASR\\Own.asr\n",
+    );
+    fs.insert("/pkg/ASR/Own.asr", "SECTORFILE:\\Sector\\Test.sct\nAirports:EGLL:symbol\n");
+    fs.insert(
+        "/pkg/Broken.prf",
+        "Settings\tSettingsfileSYMBOLOGY\t\\Settings\\Symbology.txt\nSettings\tSettingsfileTAGS\t\\Settings\\Tags.txt\nSettings\tsector\t\\Sector\\Missing.sct\nASRFastKeys\t1\t\\ASR\\Bad.asr\nASRFastKeys\t2\t\\ASR\\Ground.asr\nASRFastKeys\t3\t\\ASR\\Missing.asr\nASRFastKeys\t4\t\\ASR\\Sector.asr\nASRFastKeys\t5\t\\ASR\\Sector2.asr\n",
+    );
+    fs.insert("/pkg/Settings/Tags.txt", "TAGTYPE:Assumed\nTAGFAMILY:Matias\n");
+    // Truncated lines and bad numbers are skipped without losing the rest of the ASR
+    fs.insert("/pkg/ASR/Bad.asr", "DisplayTypeName\nWINDOWAREA:51.0:-1.0\nDisplayTypeNeedRadarContent:yes\nAirports:EGLL:symbol\n");
+    fs.insert("/pkg/ASR/Sector.asr", "SECTORFILE:\\Sector\\Bad.sct\nAirports:EGLL:symbol\n");
+    fs.insert("/pkg/ASR/Sector2.asr", "SECTORFILE:\\Sector\\Bad.sct\nAirports:EGLL:name\n");
+    fs.insert("/pkg/Sector/Bad.sct", "");
+    fs.insert("/pkg/Unreadable.prf", "");
+    let fs = UnreadableFileSystem {
+        inner: fs,
+        unreadable: vec![PathBuf::from("/pkg/Unreadable.prf"), PathBuf::from("/pkg/Sector/Bad.sct")],
+    };
+
+    let mut es = EuroScopeLoader::try_new_from_dir_with_fs(Arc::new(fs), "/pkg").unwrap();
+    assert_eq!(es.prfs.len(), 2);
+    assert_eq!(es.failed_prfs.len(), 1);
+    let result = es.try_read().unwrap();
+    assert_eq!(result.profiles.len(), 2);
+
+    // The PRF that could not be opened still has a report
+    assert_eq!(result.failed_profiles.len(), 1);
+    assert_eq!(result.failed_profiles[0].prf_file, "/pkg/Unreadable.prf");
+    assert!(result.failed_profiles[0].load_error.as_deref().unwrap().contains("Permission denied"));
+    assert_eq!(result.reports().count(), 3);
+
+    // The sector of the PRF and of its ASR is only reported once
+    let good = result.profiles.iter().find(|profile| profile.prf_name == "Test").unwrap();
+    assert!(good.report.is_complete());
+    assert_eq!(
+        good.report.loaded_files,
+        vec!["/pkg/Settings/Symbology.txt", "/pkg/Sector/Test.sct", "/pkg/Sector/Test.ese", "/pkg/ASR/Ground.asr", "/pkg/ASR/Own.asr"]
+    );
+
+    let broken = result.profiles.iter().find(|profile| profile.prf_name == "Broken").unwrap();
+    let report = &broken.report;
+    assert!(!report.is_complete());
+    assert_eq!(report.missing_files, vec!["\\Sector\\Missing.sct", "\\ASR\\Missing.asr"]);
+    assert_eq!(report.failed_files.len(), 1);
+    assert_eq!(report.failed_files[0].0, "/pkg/Sector/Bad.sct");
+    assert_eq!(
+        report.line_errors.iter().map(|(file, line_number, _, _)| (file.as_str(), *line_number)).collect::<Vec<_>>(),
+        vec![("/pkg/Settings/Tags.txt", 1), ("/pkg/ASR/Bad.asr", 1), ("/pkg/ASR/Bad.asr", 2), ("/pkg/ASR/Bad.asr", 3)]
+    );
+    assert_eq!(
+        report.loaded_files,
+        vec!["/pkg/Settings/Symbology.txt", "/pkg/Settings/Tags.txt", "/pkg/ASR/Bad.asr", "/pkg/ASR/Ground.asr", "/pkg/ASR/Sector.asr", "/pkg/ASR/Sector2.asr"]
+    );
+
+    // The partial profile still has the files that loaded
+    let mut asr_keys = broken.asrs.keys().map(String::as_str).collect::<Vec<_>>();
+    asr_keys.sort();
+    assert_eq!(asr_keys, vec!["1", "2", "4", "5"]);
+    assert_eq!(broken.asrs["1"].display_items.len(), 1);
+    assert_eq!(broken.asrs["4"].sector_file_id.as_deref(), Some(""));
+    assert!(broken.tags.as_ref().unwrap().family("Matias").is_some());
+    assert!(!broken.symbology.symbols.is_empty());
+
+    let package = AtcScopePackage::try_from(result).unwrap();
+    assert_eq!(package.facilities.len(), 2);
+}
+
+#[test]
+fn test_ese_positions() {
+    let ese = r#"[POSITIONS]
+LON_S_CTR:London Control:129.425:S:S:LON:CTR:-:-:0401:0467:N051.00.00.000:W000.30.00.000
+EGLL_TWR:Heathrow Tower:118.500:LL:T:EGLL:TWR:-:-:
+LON_E_CTR:London Control:199.998:E:E:LON:CTR:-:-:
+LON_W_CTR:London Control:118.5:W:W:LON:CTR:-:-:
+LON_X_CTR:London Control:abc.def:X:X:LON:CTR:-:-:
+"#;
+    let ese = EseReader::new(ese.as_bytes()).try_read().unwrap();
+    let positions = ese.atc_positions.iter().map(package::position::AtcPosition::from_es_position).collect::<Vec<_>>();
+    assert_eq!(
+        positions.iter().map(|position| position.frequency).collect::<Vec<_>>(),
+        vec![Some((129, 425)), Some((118, 500)), Some((199, 998)), Some((118, 500)), None]
+    );
+    assert_eq!(positions[0].callsign.as_deref(), Some("LON_S_CTR"));
+    assert_eq!(positions[0].radio_name.as_deref(), Some("London Control"));
+    assert_eq!(positions[0].identifier.as_deref(), Some("S"));
+    assert_eq!(positions[0].squawk_range, Some((401, 467)));
+    assert_eq!(positions[0].visibility_centers.len(), 1);
+    assert_eq!(positions[1].callsign.as_deref(), Some("EGLL_T_TWR"));
+    assert_eq!(positions[1].squawk_range, None);
+
+    // Positions are attached to the facility of each PRF using the sector file
+    let mut fs = test_memory_package();
+    fs.insert("/pkg/Sector/Test.ese", "[POSITIONS]\nEGLL_TWR:Heathrow Tower:118.500:LL:T:EGLL:TWR:-:-:\n");
+    let mut es = EuroScopeLoader::try_new_from_dir_with_fs(Arc::new(fs), "/pkg").unwrap();
+    let package = AtcScopePackage::try_from(es.try_read().unwrap()).unwrap();
+    assert_eq!(package.facilities[0].positions.len(), 1);
+    assert_eq!(package.facilities[0].positions[0].name, "EGLL_TWR");
+    assert_eq!(package.facilities[0].positions[0].frequency, Some((118, 500)));
+
+    // Packages exported before positions were added still load
+    let facility: package::AtcFacility = serde_json::from_str(r#"{"name":"Old","displays":[],"child_facilities":[]}"#).unwrap();
+    assert!(facility.positions.is_empty());
+}